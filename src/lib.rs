@@ -1,49 +1,182 @@
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(any(test, feature = "std"))]
+extern crate std;
+
 use core::fmt;
 
 macro_rules! escape_code {
     ($doc:expr, $name:ident, $value:expr) => {
         #[doc = $doc]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         pub struct $name;
 
+        impl $name {
+            /// The escape code as a string, usable in `const` contexts.
+            pub const SEQUENCE: &'static str = $value;
+        }
+
         impl fmt::Display for $name {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                write!(f, $value)
+                f.write_str(Self::SEQUENCE)
             }
         }
     };
 }
 
+macro_rules! dec_private_mode_code {
+    ($doc:expr, $name:ident, $code:literal, set) => {
+        escape_code!($doc, $name, concat!("\x1B[?", $code, "h"));
+    };
+    ($doc:expr, $name:ident, $code:literal, reset) => {
+        escape_code!($doc, $name, concat!("\x1B[?", $code, "l"));
+    };
+}
+
+macro_rules! count_code {
+    ($($name:ident = $default:literal),* $(,)?) => {
+        $(
+            impl $name {
+                pub const fn new(count: u16) -> Self {
+                    $name(count)
+                }
+
+                /// Create from a `usize` count, clamping it to `u16::MAX` instead of truncating it.
+                pub fn saturating(count: usize) -> Self {
+                    $name(if count > u16::MAX as usize { u16::MAX } else { count as u16 })
+                }
+            }
+
+            impl Default for $name {
+                #[doc = concat!("Returns a count of ", stringify!($default), ".")]
+                fn default() -> Self {
+                    $name($default)
+                }
+            }
+        )*
+    };
+}
+
+// Counts default to 1, the same as terminals use when the count is omitted, except for erasing lines which defaults to erasing nothing
+count_code!(
+    CursorUp = 1,
+    CursorDown = 1,
+    CursorForward = 1,
+    CursorBackward = 1,
+    CursorNextLineBy = 1,
+    CursorPrevLineBy = 1,
+    CursorColumn = 1,
+    CursorRow = 1,
+    CursorForwardTab = 1,
+    CursorBackwardTab = 1,
+    EraseLines = 0,
+    EraseLinesFull = 0,
+    EraseLinesSafe = 0,
+    MoveToLineStart = 1,
+    EraseChars = 1,
+    InsertLines = 1,
+    DeleteLines = 1,
+    InsertChars = 1,
+    DeleteChars = 1,
+    RepeatLast = 1,
+    ScrollUpBy = 1,
+    ScrollDownBy = 1,
+);
+
+/// The error returned when parsing an escape code from a string fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseError;
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid escape code")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+fn parse_count(input: &str, final_byte: u8) -> Result<u16, ParseError> {
+    let params = input.as_bytes().strip_prefix(b"\x1B[").and_then(|rest| rest.strip_suffix(&[final_byte][..])).ok_or(ParseError)?;
+    parse_param(params, 1).ok_or(ParseError)
+}
+
+macro_rules! parse_count_code {
+    ($($name:ident => $final_byte:literal),* $(,)?) => {
+        $(
+            impl<'a> TryFrom<&'a str> for $name {
+                type Error = ParseError;
+
+                /// Parse the escape code, e.g. as output by `Display`. A missing count defaults to 1.
+                fn try_from(input: &'a str) -> Result<Self, ParseError> {
+                    parse_count(input, $final_byte).map($name)
+                }
+            }
+        )*
+    };
+}
+
+parse_count_code!(
+    CursorUp => b'A',
+    CursorDown => b'B',
+    CursorForward => b'C',
+    CursorBackward => b'D',
+    CursorNextLineBy => b'E',
+    CursorPrevLineBy => b'F',
+    CursorColumn => b'G',
+    CursorRow => b'd',
+    CursorForwardTab => b'I',
+    CursorBackwardTab => b'Z',
+);
+
 /// Set the absolute position of the cursor. x=0 y=0 is the top left of the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CursorTo {
     TopLeft,
     AbsoluteX(u16),
     AbsoluteXY(u16, u16),
+    /// Move to row `y` without changing the column.
+    AbsoluteY(u16),
+    /// Unlike the other variants, `row` and `col` are 1-based, so row=1 col=1 is the top left of the screen.
+    RowCol(u16, u16),
 }
 
 impl fmt::Display for CursorTo {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             CursorTo::TopLeft => write!(f, "\x1B[{};{}H", 1, 1),
-            CursorTo::AbsoluteX(x) => write!(f, "\x1B[{}G", x + 1),
-            CursorTo::AbsoluteXY(x, y) => write!(f, "\x1B[{};{}H", y + 1, x + 1),
+            CursorTo::AbsoluteX(x) => write!(f, "\x1B[{}G", u32::from(x) + 1),
+            CursorTo::AbsoluteXY(x, y) => write!(f, "\x1B[{};{}H", u32::from(y) + 1, u32::from(x) + 1),
+            CursorTo::AbsoluteY(y) => write!(f, "\x1B[{}d", u32::from(y) + 1),
+            CursorTo::RowCol(row, col) => write!(f, "\x1B[{};{}H", row, col),
         }
     }
 }
 
 /// Set the position of the cursor relative to its current position.
+///
+/// Moving zero steps along an axis produces no output for that axis, rather than e.g. `\x1B[0C` which many terminals treat as a move of one step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CursorMove {
     X(i16),
+    /// Move along the x axis first, then along the y axis. This order is guaranteed.
     XY(i16, i16),
     Y(i16),
+    /// Move along the y axis first, then along the x axis. Takes `(x, y)` in the same order as `XY`.
+    YThenX(i16, i16),
 }
 
 impl fmt::Display for CursorMove {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             CursorMove::X(x) if x > 0 => write!(f, "\x1B[{}C", x),
-            CursorMove::X(x) if x < 0 => write!(f, "\x1B[{}D", -x),
+            CursorMove::X(x) if x < 0 => write!(f, "\x1B[{}D", -(x as i32)),
             CursorMove::X(_) => fmt::Result::Ok(()),
 
             CursorMove::XY(x, y) => {
@@ -53,13 +186,31 @@ impl fmt::Display for CursorMove {
             }
 
             CursorMove::Y(y) if y > 0 => write!(f, "\x1B[{}B", y),
-            CursorMove::Y(y) if y < 0 => write!(f, "\x1B[{}A", -y),
+            CursorMove::Y(y) if y < 0 => write!(f, "\x1B[{}A", -(y as i32)),
             CursorMove::Y(_) => fmt::Result::Ok(()),
+
+            CursorMove::YThenX(x, y) => {
+                CursorMove::Y(y).fmt(f)?;
+                CursorMove::X(x).fmt(f)?;
+                fmt::Result::Ok(())
+            }
         }
     }
 }
 
+/// Set the absolute position of the cursor, same as `CursorTo::AbsoluteXY`.
+pub fn cursor_to(x: u16, y: u16) -> impl fmt::Display {
+    CursorTo::AbsoluteXY(x, y)
+}
+
+/// Set the position of the cursor relative to its current position, same as `CursorMove::XY`.
+pub fn cursor_move(x: i16, y: i16) -> impl fmt::Display {
+    CursorMove::XY(x, y)
+}
+
 /// Move cursor up a specific amount of rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CursorUp(pub u16);
 
 impl fmt::Display for CursorUp {
@@ -69,6 +220,8 @@ impl fmt::Display for CursorUp {
 }
 
 /// Move cursor down a specific amount of rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CursorDown(pub u16);
 
 impl fmt::Display for CursorDown {
@@ -78,6 +231,8 @@ impl fmt::Display for CursorDown {
 }
 
 /// Move cursor forward a specific amount of rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CursorForward(pub u16);
 
 impl fmt::Display for CursorForward {
@@ -87,6 +242,8 @@ impl fmt::Display for CursorForward {
 }
 
 /// Move cursor backward a specific amount of rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CursorBackward(pub u16);
 
 impl fmt::Display for CursorBackward {
@@ -95,16 +252,163 @@ impl fmt::Display for CursorBackward {
     }
 }
 
+/// Move cursor down a specific amount of rows and to the start of the line. Unlike `CursorDown`, this also resets the column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CursorNextLineBy(pub u16);
+
+impl fmt::Display for CursorNextLineBy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}E", self.0)
+    }
+}
+
+/// Move cursor up a specific amount of rows and to the start of the line. Unlike `CursorUp`, this also resets the column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CursorPrevLineBy(pub u16);
+
+impl fmt::Display for CursorPrevLineBy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}F", self.0)
+    }
+}
+
+/// Move cursor to a specific column. Unlike `CursorTo`, the column is 1-based, so column 1 is the left side of the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CursorColumn(pub u16);
+
+impl fmt::Display for CursorColumn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}G", self.0)
+    }
+}
+
+/// Move cursor to a specific row. Unlike `CursorTo`, the row is 1-based, so row 1 is the top of the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CursorRow(pub u16);
+
+impl fmt::Display for CursorRow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}d", self.0)
+    }
+}
+
+/// Move cursor forward a specific amount of tab stops.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CursorForwardTab(pub u16);
+
+impl fmt::Display for CursorForwardTab {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}I", self.0)
+    }
+}
+
+/// Move cursor backward a specific amount of tab stops.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CursorBackwardTab(pub u16);
+
+impl fmt::Display for CursorBackwardTab {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}Z", self.0)
+    }
+}
+
+escape_code!("Set a tab stop at the current cursor column.", SetTabStop, "\x1BH");
+escape_code!("Clear the tab stop at the current cursor column.", ClearTabStop, "\x1B[0g");
+escape_code!("Clear all tab stops.", ClearAllTabStops, "\x1B[3g");
+
 escape_code!("Move cursor to the left side.", CursorLeft, "\x1B[1000D");
 escape_code!("Save cursor position.", CursorSavePosition, "\x1B[s");
 escape_code!("Restore saved cursor position.", CursorRestorePosition, "\x1B[u");
 escape_code!("Get cursor position.", CursorGetPosition, "\x1B[6n");
 escape_code!("Move cursor to the next line.", CursorNextLine, "\x1B[E");
 escape_code!("Move cursor to the previous line.", CursorPrevLine, "\x1B[F");
-escape_code!("Hide cursor.", CursorHide, "\x1B[?25l");
-escape_code!("Show cursor.", CursorShow, "\x1B[?25h");
+dec_private_mode_code!("Hide cursor.", CursorHide, 25, reset);
+dec_private_mode_code!("Show cursor.", CursorShow, 25, set);
+
+/// Set the shape of the cursor using DECSCUSR.
+///
+/// The variants map to the sequences `\x1B[1 q` (blinking block) through `\x1B[6 q` (steady bar).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CursorShape {
+    /// Blinking block, `\x1B[1 q`.
+    BlockBlink,
+    /// Steady block, `\x1B[2 q`.
+    BlockSteady,
+    /// Blinking underline, `\x1B[3 q`.
+    UnderlineBlink,
+    /// Steady underline, `\x1B[4 q`.
+    UnderlineSteady,
+    /// Blinking bar, `\x1B[5 q`.
+    BarBlink,
+    /// Steady bar, `\x1B[6 q`.
+    BarSteady,
+}
+
+impl fmt::Display for CursorShape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = match *self {
+            CursorShape::BlockBlink => 1,
+            CursorShape::BlockSteady => 2,
+            CursorShape::UnderlineBlink => 3,
+            CursorShape::UnderlineSteady => 4,
+            CursorShape::BarBlink => 5,
+            CursorShape::BarSteady => 6,
+        };
+
+        write!(f, "\x1B[{} q", code)
+    }
+}
+
+escape_code!(
+    "Reset the cursor to the default shape configured in the terminal, e.g. when an editor exits. Setting `CursorShape::BlockBlink` instead would force a blinking block even if the user prefers e.g. a steady bar.",
+    CursorShapeDefault,
+    "\x1B[0 q"
+);
+
+dec_private_mode_code!(
+    "Make the cursor blink. This overrides the blinking of the current `CursorShape`, and may in turn be overridden by setting a new `CursorShape`.",
+    EnableCursorBlink,
+    12,
+    set
+);
+dec_private_mode_code!(
+    "Make the cursor steady. This overrides the blinking of the current `CursorShape`, and may in turn be overridden by setting a new `CursorShape`.",
+    DisableCursorBlink,
+    12,
+    reset
+);
+
+escape_code!(
+    "Move cursor to the left side using a carriage return. Unlike `CursorLeft`, this doesn't rely on the terminal clamping a large move.",
+    CarriageReturn,
+    "\r"
+);
+
+escape_code!(
+    "Save cursor position and text attributes using the DEC sequence, which is more widely and consistently supported than `CursorSavePosition`.",
+    CursorSavePositionDec,
+    "\x1B7"
+);
+escape_code!(
+    "Restore saved cursor position and text attributes using the DEC sequence, which is more widely and consistently supported than `CursorRestorePosition`.",
+    CursorRestorePositionDec,
+    "\x1B8"
+);
 
 /// Erase from the current cursor position up the specified amount of rows.
+///
+/// `EraseLines(0)`, which is also the `Default`, produces no output and leaves the current row untouched, use `EraseLines(1)` to erase only the current row.
+///
+/// The cursor is left at the start of the topmost erased row. If there are fewer rows above the cursor than requested, e.g. at the top of the screen or of a scroll region, `CursorUp` stops at the edge and the top row is erased repeatedly, leaving the cursor higher up than the caller expects. See `EraseLinesSafe` for a variant that restores the cursor afterwards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EraseLines(pub u16);
 
 impl fmt::Display for EraseLines {
@@ -122,63 +426,3571 @@ impl fmt::Display for EraseLines {
     }
 }
 
+/// Erase the specified amount of rows entirely, from the current row and up. Unlike `EraseLines`, this also erases the content before the cursor on the current row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EraseLinesFull(pub u16);
+
+impl fmt::Display for EraseLinesFull {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for idx in 0..self.0 {
+            if idx > 0 {
+                write!(f, "{}", CursorUp(1))?;
+            }
+
+            write!(f, "{}", EraseLine)?;
+        }
+
+        fmt::Result::Ok(())
+    }
+}
+
+/// Erase from the current cursor position up the specified amount of rows, like `EraseLines`, but save the cursor with `CursorSavePositionDec` first and restore it with `CursorRestorePositionDec` afterwards. The cursor ends up where it started, even when `CursorUp` stops at the top of the screen or of a scroll region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EraseLinesSafe(pub u16);
+
+impl fmt::Display for EraseLinesSafe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 == 0 {
+            return fmt::Result::Ok(());
+        }
+
+        write!(f, "{}{}{}", CursorSavePositionDec, EraseLines(self.0), CursorRestorePositionDec)
+    }
+}
+
+/// Move the cursor up the specified amount of rows and to the start of that row, without erasing anything. Use this to redraw e.g. a menu in place by overwriting it, and `EraseLines` when the previous content should be removed first.
+///
+/// `MoveToLineStart(0)` moves to the start of the current row with `CarriageReturn`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoveToLineStart(pub u16);
+
+impl fmt::Display for MoveToLineStart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 == 0 {
+            return CarriageReturn.fmt(f);
+        }
+
+        CursorPrevLineBy(self.0).fmt(f)
+    }
+}
+
 escape_code!("Erase from the current cursor position to the end of the current line.", EraseEndLine, "\x1B[K");
 escape_code!("Erase from the current cursor position to the start of the current line.", EraseStartLine, "\x1B[1K");
 escape_code!("Erase the entire current line.", EraseLine, "\x1B[2K");
+escape_code!(
+    "Move cursor to the left side and erase the entire current line, for redrawing a line in place.",
+    ClearCurrentLine,
+    "\r\x1B[2K"
+);
+
+/// Redraw the current line with `text`, e.g. for a progress bar. Outputs `ClearCurrentLine` followed by `text` without a trailing newline, so that the next `ProgressRedraw` overwrites it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProgressRedraw<'a> {
+    pub text: &'a str,
+}
+
+impl<'a> fmt::Display for ProgressRedraw<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", ClearCurrentLine, self.text)
+    }
+}
+
+/// Erase part of the current line (EL), as a single type for all modes.
+///
+/// `EraseEndLine`, `EraseStartLine` and `EraseLine` are equivalent to `ToEnd`, `ToStart` and `All`, and can be converted with `From`. Note that `EraseEndLine` outputs `\x1B[K`, which is the same as `\x1B[0K`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EraseInLine {
+    /// Erase from the current cursor position to the end of the line, `\x1B[0K`.
+    ToEnd,
+    /// Erase from the current cursor position to the start of the line, `\x1B[1K`.
+    ToStart,
+    /// Erase the entire line, `\x1B[2K`.
+    All,
+}
+
+impl fmt::Display for EraseInLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mode = match *self {
+            EraseInLine::ToEnd => 0,
+            EraseInLine::ToStart => 1,
+            EraseInLine::All => 2,
+        };
+
+        write!(f, "\x1B[{}K", mode)
+    }
+}
+
+impl From<EraseEndLine> for EraseInLine {
+    fn from(_: EraseEndLine) -> Self {
+        EraseInLine::ToEnd
+    }
+}
+
+impl From<EraseStartLine> for EraseInLine {
+    fn from(_: EraseStartLine) -> Self {
+        EraseInLine::ToStart
+    }
+}
+
+impl From<EraseLine> for EraseInLine {
+    fn from(_: EraseLine) -> Self {
+        EraseInLine::All
+    }
+}
+
+/// Erase a specific amount of characters from the current cursor position, without moving the cursor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EraseChars(pub u16);
+
+impl fmt::Display for EraseChars {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}X", self.0)
+    }
+}
+
+/// Erase a rectangular area of the screen (DECERA), from `top`/`left` to `bottom`/`right` inclusive. Like `CursorTo`, coordinates are 0-based.
+///
+/// This is a DEC private feature with limited support in terminal emulators.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EraseRectangle {
+    pub top: u16,
+    pub left: u16,
+    pub bottom: u16,
+    pub right: u16,
+}
+
+impl fmt::Display for EraseRectangle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "\x1B[{};{};{};{}$z",
+            u32::from(self.top) + 1,
+            u32::from(self.left) + 1,
+            u32::from(self.bottom) + 1,
+            u32::from(self.right) + 1
+        )
+    }
+}
+
+/// Fill a rectangular area of the screen with a character (DECFRA), from `top`/`left` to `bottom`/`right` inclusive. Like `CursorTo`, coordinates are 0-based.
+///
+/// This is a DEC private feature with limited support in terminal emulators.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FillRectangle {
+    pub ch: char,
+    pub top: u16,
+    pub left: u16,
+    pub bottom: u16,
+    pub right: u16,
+}
+
+impl FillRectangle {
+    /// Returns `None` if `ch` isn't a printable character that DECFRA accepts, i.e. in the range 32 to 126 or 160 to 255.
+    pub fn new(ch: char, top: u16, left: u16, bottom: u16, right: u16) -> Option<Self> {
+        match ch as u32 {
+            32..=126 | 160..=255 => Some(FillRectangle { ch, top, left, bottom, right }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FillRectangle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "\x1B[{};{};{};{};{}$x",
+            self.ch as u32,
+            u32::from(self.top) + 1,
+            u32::from(self.left) + 1,
+            u32::from(self.bottom) + 1,
+            u32::from(self.right) + 1
+        )
+    }
+}
+
+/// Insert a specific amount of blank lines at the current cursor position, pushing the following lines down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InsertLines(pub u16);
+
+impl fmt::Display for InsertLines {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}L", self.0)
+    }
+}
+
+/// Delete a specific amount of lines at the current cursor position, pulling the following lines up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeleteLines(pub u16);
+
+impl fmt::Display for DeleteLines {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}M", self.0)
+    }
+}
+
+/// Insert a specific amount of blank characters at the current cursor position, pushing the rest of the line right.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InsertChars(pub u16);
+
+impl fmt::Display for InsertChars {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}@", self.0)
+    }
+}
+
+/// Delete a specific amount of characters at the current cursor position, pulling the rest of the line left.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeleteChars(pub u16);
+
+impl fmt::Display for DeleteChars {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}P", self.0)
+    }
+}
+
+/// Repeat the preceding printed character a specific amount of times (REP). Support for this varies between terminals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RepeatLast(pub u16);
+
+impl fmt::Display for RepeatLast {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}b", self.0)
+    }
+}
 
 escape_code!("Erase the screen from the current line down to the bottom of the screen.", EraseDown, "\x1B[J");
 escape_code!("Erase the screen from the current line up to the top of the screen.", EraseUp, "\x1B[1J");
-escape_code!("Erase the screen and move the cursor the top left position.", EraseScreen, "\x1B[2J");
+escape_code!(
+    "Erase the entire screen. Most terminals leave the cursor where it was, use `ClearAndHome` to also move it to the top left position.",
+    EraseScreen,
+    "\x1B[2J"
+);
+escape_code!("Erase the scrollback buffer. Unlike `EraseScreen`, this doesn't erase the visible screen.", EraseScrollback, "\x1B[3J");
+escape_code!("Erase both the screen and the scrollback buffer.", ClearScreenAndScrollback, "\x1B[2J\x1B[3J");
+escape_code!(
+    "Erase the screen and move the cursor to the top left position. `EraseScreen` alone leaves the cursor where it was in most terminals, so this also moves it there explicitly with `\\x1B[H`.",
+    ClearAndHome,
+    "\x1B[2J\x1B[H"
+);
 escape_code!("Scroll display up one line.", ScrollUp, "\x1B[S");
 escape_code!("Scroll display down one line.", ScrollDown, "\x1B[T");
 
-escape_code!("Clear the terminal screen.", ClearScreen, "\u{001b}c");
-escape_code!("Enter the [alternative screen](https://terminalguide.namepad.de/mode/p47/).", EnterAlternativeScreen, "\x1B[?1049h");
-escape_code!("Exit the [alternative screen](https://terminalguide.namepad.de/mode/p47/).", ExitAlternativeScreen, "\x1B[?1049l");
-escape_code!("Output a beeping sound.", Beep, "\u{0007}");
+/// Erase part of the screen (ED), as a single type for all modes.
+///
+/// `EraseDown`, `EraseUp`, `EraseScreen` and `EraseScrollback` are equivalent to `Below`, `Above`, `All` and `Scrollback`, and can be converted with `From`. They are kept as separate codes so that their output doesn't change, note that `EraseDown` outputs `\x1B[J` while `EraseDisplay::Below` outputs the equivalent `\x1B[0J`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EraseDisplay {
+    /// Erase from the current cursor position to the bottom of the screen, `\x1B[0J`.
+    Below,
+    /// Erase from the current cursor position to the top of the screen, `\x1B[1J`.
+    Above,
+    /// Erase the entire screen, `\x1B[2J`.
+    All,
+    /// Erase the scrollback buffer, `\x1B[3J`.
+    Scrollback,
+}
 
-#[cfg(test)]
-extern crate std;
+impl fmt::Display for EraseDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mode = match *self {
+            EraseDisplay::Below => 0,
+            EraseDisplay::Above => 1,
+            EraseDisplay::All => 2,
+            EraseDisplay::Scrollback => 3,
+        };
 
-#[cfg(test)]
-mod tests {
-    use std::{io::Write, string::String, vec::Vec};
+        write!(f, "\x1B[{}J", mode)
+    }
+}
 
-    macro_rules! assert_escape_output {
-        ($name:ident, $code:expr, $expected:expr) => {
-            #[test]
-            fn $name() {
-                let mut buf = Vec::new();
-                write!(buf, "{}", $code).unwrap();
+impl From<EraseDown> for EraseDisplay {
+    fn from(_: EraseDown) -> Self {
+        EraseDisplay::Below
+    }
+}
 
-                let result = String::from_utf8(buf).unwrap();
-                assert_eq!(result, $expected);
-            }
-        };
+impl From<EraseUp> for EraseDisplay {
+    fn from(_: EraseUp) -> Self {
+        EraseDisplay::Above
     }
+}
 
-    assert_escape_output!(cursor_up_1, super::CursorUp(1), "\x1B[1A");
-    assert_escape_output!(cursor_up_23, super::CursorUp(23), "\x1B[23A");
+impl From<EraseScreen> for EraseDisplay {
+    fn from(_: EraseScreen) -> Self {
+        EraseDisplay::All
+    }
+}
 
-    assert_escape_output!(cursor_down_1, super::CursorDown(1), "\x1B[1B");
-    assert_escape_output!(cursor_down_23, super::CursorDown(23), "\x1B[23B");
+impl From<EraseScrollback> for EraseDisplay {
+    fn from(_: EraseScrollback) -> Self {
+        EraseDisplay::Scrollback
+    }
+}
 
-    assert_escape_output!(cursor_forward_1, super::CursorForward(1), "\x1B[1C");
-    assert_escape_output!(cursor_forward_23, super::CursorForward(23), "\x1B[23C");
+/// Scroll display up a specific amount of lines. Note that `ScrollUpBy(1)` outputs `\x1B[1S`, while `ScrollUp` outputs `\x1B[S`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScrollUpBy(pub u16);
 
-    assert_escape_output!(cursor_backward_1, super::CursorBackward(1), "\x1B[1D");
-    assert_escape_output!(cursor_backward_23, super::CursorBackward(23), "\x1B[23D");
+impl fmt::Display for ScrollUpBy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}S", self.0)
+    }
+}
 
-    assert_escape_output!(cursor_left, super::CursorLeft, "\x1B[1000D");
-    assert_escape_output!(cursor_save_position, super::CursorSavePosition, "\x1B[s");
-    assert_escape_output!(cursor_restore_position, super::CursorRestorePosition, "\x1B[u");
-    assert_escape_output!(cursor_get_position, super::CursorGetPosition, "\x1B[6n");
-    assert_escape_output!(cursor_next_line, super::CursorNextLine, "\x1B[E");
-    assert_escape_output!(cursor_prev_line, super::CursorPrevLine, "\x1B[F");
-    assert_escape_output!(cursor_hide, super::CursorHide, "\x1B[?25l");
-    assert_escape_output!(cursor_show, super::CursorShow, "\x1B[?25h");
+/// Scroll display down a specific amount of lines. Note that `ScrollDownBy(1)` outputs `\x1B[1T`, while `ScrollDown` outputs `\x1B[T`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScrollDownBy(pub u16);
 
-    assert_escape_output!(erase_lines_1, super::EraseLines(1), "\x1B[1000D\x1B[K");
-    assert_escape_output!(erase_lines_2, super::EraseLines(2), "\x1B[1000D\x1B[K\x1B[1A\x1B[1000D\x1B[K");
-    assert_escape_output!(erase_lines_3, super::EraseLines(3), "\x1B[1000D\x1B[K\x1B[1A\x1B[1000D\x1B[K\x1B[1A\x1B[1000D\x1B[K");
+impl fmt::Display for ScrollDownBy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}T", self.0)
+    }
+}
+
+/// Restrict scrolling to the rows from `top` to `bottom`, inclusive. Like `CursorTo`, rows are 0-based, so top=0 is the first row of the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetScrollRegion {
+    pub top: u16,
+    pub bottom: u16,
+}
+
+impl fmt::Display for SetScrollRegion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{};{}r", u32::from(self.top) + 1, u32::from(self.bottom) + 1)
+    }
+}
+
+escape_code!("Reset the scroll region to the entire screen.", ResetScrollRegion, "\x1B[r");
+
+escape_code!(
+    "Reset the terminal to its initial state (RIS), clearing the screen and resetting all modes and attributes.",
+    FullReset,
+    "\x1Bc"
+);
+escape_code!("Reset modes and attributes of the terminal (DECSTR), without clearing the screen.", SoftReset, "\x1B[!p");
+
+/// Clear the terminal screen. Same as `FullReset`.
+#[deprecated(note = "use `FullReset` instead, this also resets the terminal rather than just clearing the screen")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClearScreen;
+
+#[allow(deprecated)]
+impl ClearScreen {
+    /// The escape code as a string, usable in `const` contexts.
+    pub const SEQUENCE: &'static str = FullReset::SEQUENCE;
+}
+
+#[allow(deprecated)]
+impl fmt::Display for ClearScreen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        FullReset.fmt(f)
+    }
+}
+
+dec_private_mode_code!("Enter the [alternative screen](https://terminalguide.namepad.de/mode/p47/).", EnterAlternativeScreen, 1049, set);
+dec_private_mode_code!("Exit the [alternative screen](https://terminalguide.namepad.de/mode/p47/).", ExitAlternativeScreen, 1049, reset);
+escape_code!(
+    "Set up the terminal for a full screen application, by outputting `EnterAlternativeScreen`, `CursorHide` and `EraseScreen`. Undo with `ExitTuiMode`.",
+    EnterTuiMode,
+    "\x1B[?1049h\x1B[?25l\x1B[2J"
+);
+escape_code!(
+    "Restore the terminal after `EnterTuiMode`, by outputting `CursorShow` and `ExitAlternativeScreen`.",
+    ExitTuiMode,
+    "\x1B[?25h\x1B[?1049l"
+);
+escape_code!("Output a beeping sound.", Beep, "\u{0007}");
+
+/// A DEC private mode, which can be set with `\x1B[?{n}h` and reset with `\x1B[?{n}l`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecPrivateMode {
+    /// Invert the colors of the entire screen, mode 5.
+    ReverseScreen,
+    /// Automatic wrapping of text at the end of the line, mode 7.
+    LineWrap,
+    /// Blinking cursor, mode 12.
+    CursorBlink,
+    /// Visible cursor, mode 25.
+    CursorVisible,
+    /// Reporting of mouse button presses and releases, mode 1000.
+    MouseTracking,
+    /// Reporting of mouse movement while a button is held down, mode 1002.
+    MouseButtonEvent,
+    /// Reporting of all mouse movement, mode 1003.
+    MouseAnyEvent,
+    /// Reporting of focus events, mode 1004.
+    FocusReporting,
+    /// SGR encoding of mouse reports, mode 1006.
+    SgrMouse,
+    /// The alternative screen, mode 1049.
+    AlternativeScreen,
+    /// Bracketed paste, mode 2004.
+    BracketedPaste,
+    /// Synchronized update, mode 2026.
+    SynchronizedUpdate,
+    /// Any other mode, by number.
+    Other(u16),
+}
+
+impl DecPrivateMode {
+    /// The number of the mode.
+    pub fn code(self) -> u16 {
+        match self {
+            DecPrivateMode::ReverseScreen => 5,
+            DecPrivateMode::LineWrap => 7,
+            DecPrivateMode::CursorBlink => 12,
+            DecPrivateMode::CursorVisible => 25,
+            DecPrivateMode::MouseTracking => 1000,
+            DecPrivateMode::MouseButtonEvent => 1002,
+            DecPrivateMode::MouseAnyEvent => 1003,
+            DecPrivateMode::FocusReporting => 1004,
+            DecPrivateMode::SgrMouse => 1006,
+            DecPrivateMode::AlternativeScreen => 1049,
+            DecPrivateMode::BracketedPaste => 2004,
+            DecPrivateMode::SynchronizedUpdate => 2026,
+            DecPrivateMode::Other(code) => code,
+        }
+    }
+
+    /// Set (enable) the mode.
+    pub fn set(self) -> impl fmt::Display {
+        DecPrivateModeChange { code: self.code(), suffix: 'h' }
+    }
+
+    /// Reset (disable) the mode.
+    pub fn reset(self) -> impl fmt::Display {
+        DecPrivateModeChange { code: self.code(), suffix: 'l' }
+    }
+}
+
+struct DecPrivateModeChange {
+    code: u16,
+    suffix: char,
+}
+
+impl fmt::Display for DecPrivateModeChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[?{}{}", self.code, self.suffix)
+    }
+}
+
+/// Ask the terminal whether a DEC private mode is set (DECRQM), e.g. `QueryDecMode(2026)` for synchronized updates. The terminal replies with e.g. `\x1B[?2026;1$y`, which can be parsed with `parse_dec_mode_reply`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueryDecMode(pub u16);
+
+impl fmt::Display for QueryDecMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[?{}$p", self.0)
+    }
+}
+
+dec_private_mode_code!(
+    "Enable bracketed paste mode. Pasted text will then be wrapped in `\\x1B[200~` and `\\x1B[201~`.",
+    EnableBracketedPaste,
+    2004,
+    set
+);
+dec_private_mode_code!("Disable bracketed paste mode.", DisableBracketedPaste, 2004, reset);
+
+dec_private_mode_code!("Enable reporting of mouse button presses and releases.", EnableMouseTracking, 1000, set);
+dec_private_mode_code!("Disable reporting of mouse button presses and releases.", DisableMouseTracking, 1000, reset);
+dec_private_mode_code!("Enable reporting of mouse movement while a button is held down.", EnableMouseButtonEvent, 1002, set);
+dec_private_mode_code!("Disable reporting of mouse movement while a button is held down.", DisableMouseButtonEvent, 1002, reset);
+dec_private_mode_code!("Enable reporting of all mouse movement.", EnableMouseAnyEvent, 1003, set);
+dec_private_mode_code!("Disable reporting of all mouse movement.", DisableMouseAnyEvent, 1003, reset);
+dec_private_mode_code!(
+    "Enable SGR encoding of mouse reports. Combine with `EnableMouseAnyEvent` for modern mouse support.",
+    EnableSgrMouse,
+    1006,
+    set
+);
+dec_private_mode_code!("Disable SGR encoding of mouse reports.", DisableSgrMouse, 1006, reset);
+
+dec_private_mode_code!("Enable automatic wrapping of text at the end of the line.", EnableLineWrap, 7, set);
+dec_private_mode_code!(
+    "Disable automatic wrapping of text at the end of the line. This affects the whole terminal until wrapping is enabled again, or the terminal is reset.",
+    DisableLineWrap,
+    7,
+    reset
+);
+
+/// Push flags onto the stack of the [kitty keyboard protocol](https://sw.kovidgoyal.net/kitty/keyboard-protocol/).
+///
+/// The flags are a bitfield of:
+///
+/// - `1`: Disambiguate escape codes
+/// - `2`: Report event types
+/// - `4`: Report alternate keys
+/// - `8`: Report all keys as escape codes
+/// - `16`: Report associated text
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PushKittyKeyboard(pub u8);
+
+impl fmt::Display for PushKittyKeyboard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[>{}u", self.0)
+    }
+}
+
+escape_code!("Pop flags from the stack of the kitty keyboard protocol.", PopKittyKeyboard, "\x1B[<u");
+escape_code!("Query the current flags of the kitty keyboard protocol.", QueryKittyKeyboard, "\x1B[?u");
+
+dec_private_mode_code!(
+    "Enable focus reporting. The terminal will then send `\\x1B[I` when it gains focus, and `\\x1B[O` when it loses focus.",
+    EnableFocusReporting,
+    1004,
+    set
+);
+dec_private_mode_code!("Disable focus reporting.", DisableFocusReporting, 1004, reset);
+
+escape_code!(
+    concat!(
+        "Switch to the DEC Special Graphics character set, for drawing boxes without Unicode.\n\n",
+        "While enabled, the following ASCII characters are drawn as box drawing glyphs:\n\n",
+        "- `q`: `─`, `x`: `│`\n",
+        "- `l`: `┌`, `k`: `┐`, `m`: `└`, `j`: `┘`\n",
+        "- `t`: `├`, `u`: `┤`, `v`: `┴`, `w`: `┬`, `n`: `┼`",
+    ),
+    EnableLineDrawing,
+    "\x1B(0"
+);
+escape_code!("Switch back to the ASCII character set after `EnableLineDrawing`.", DisableLineDrawing, "\x1B(B");
+
+dec_private_mode_code!(
+    "Begin a synchronized update, the terminal will hold off presenting output until `EndSynchronizedUpdate`. Terminals that don't support this ignore it.",
+    BeginSynchronizedUpdate,
+    2026,
+    set
+);
+dec_private_mode_code!("End a synchronized update and present the output.", EndSynchronizedUpdate, 2026, reset);
+
+dec_private_mode_code!(
+    "Invert the colors of the entire screen (DECSCNM). Unlike `Inverse`, this affects all text on the screen, not just subsequent text.",
+    ReverseScreen,
+    5,
+    set
+);
+dec_private_mode_code!("Stop inverting the colors of the entire screen.", NormalScreen, 5, reset);
+
+/// Set the foreground color of subsequent text to one of the 16 standard colors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+impl Color {
+    fn code(&self) -> u8 {
+        match *self {
+            Color::Black => 30,
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+            Color::BrightBlack => 90,
+            Color::BrightRed => 91,
+            Color::BrightGreen => 92,
+            Color::BrightYellow => 93,
+            Color::BrightBlue => 94,
+            Color::BrightMagenta => 95,
+            Color::BrightCyan => 96,
+            Color::BrightWhite => 97,
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}m", self.code())
+    }
+}
+
+escape_code!("Reset the foreground color to the terminal default.", ColorReset, "\x1B[39m");
+
+/// Set the background color of subsequent text to one of the 16 standard colors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BgColor {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+impl BgColor {
+    fn code(&self) -> u8 {
+        match *self {
+            BgColor::Black => 40,
+            BgColor::Red => 41,
+            BgColor::Green => 42,
+            BgColor::Yellow => 43,
+            BgColor::Blue => 44,
+            BgColor::Magenta => 45,
+            BgColor::Cyan => 46,
+            BgColor::White => 47,
+            BgColor::BrightBlack => 100,
+            BgColor::BrightRed => 101,
+            BgColor::BrightGreen => 102,
+            BgColor::BrightYellow => 103,
+            BgColor::BrightBlue => 104,
+            BgColor::BrightMagenta => 105,
+            BgColor::BrightCyan => 106,
+            BgColor::BrightWhite => 107,
+        }
+    }
+}
+
+impl fmt::Display for BgColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}m", self.code())
+    }
+}
+
+escape_code!("Reset the background color to the terminal default.", BgColorReset, "\x1B[49m");
+
+/// Set the foreground color of subsequent text to an index in the xterm 256-color palette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Color256(pub u8);
+
+impl fmt::Display for Color256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[38;5;{}m", self.0)
+    }
+}
+
+/// Set the background color of subsequent text to an index in the xterm 256-color palette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BgColor256(pub u8);
+
+impl fmt::Display for BgColor256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[48;5;{}m", self.0)
+    }
+}
+
+/// Set the foreground color of subsequent text to a 24-bit RGB color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColorRgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl ColorRgb {
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        ColorRgb { r, g, b }
+    }
+}
+
+impl fmt::Display for ColorRgb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[38;2;{};{};{}m", self.r, self.g, self.b)
+    }
+}
+
+impl From<(u8, u8, u8)> for ColorRgb {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        ColorRgb { r, g, b }
+    }
+}
+
+impl From<[u8; 3]> for ColorRgb {
+    fn from([r, g, b]: [u8; 3]) -> Self {
+        ColorRgb { r, g, b }
+    }
+}
+
+/// Set the background color of subsequent text to a 24-bit RGB color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BgColorRgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl BgColorRgb {
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        BgColorRgb { r, g, b }
+    }
+}
+
+impl fmt::Display for BgColorRgb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[48;2;{};{};{}m", self.r, self.g, self.b)
+    }
+}
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Get the approximate RGB color of an index in the xterm 256-color palette.
+///
+/// Indices 0-15 are the system colors, using the xterm defaults, 16-231 are a 6×6×6 color cube and 232-255 are a grayscale ramp.
+pub fn xterm256_to_rgb(index: u8) -> (u8, u8, u8) {
+    const SYSTEM: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+
+    match index {
+        0..=15 => SYSTEM[index as usize],
+        16..=231 => {
+            let cube = index - 16;
+            (CUBE_LEVELS[(cube / 36) as usize], CUBE_LEVELS[(cube / 6 % 6) as usize], CUBE_LEVELS[(cube % 6) as usize])
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let dr = r1 as i32 - r2 as i32;
+    let dg = g1 as i32 - g2 as i32;
+    let db = b1 as i32 - b2 as i32;
+
+    (dr * dr + dg * dg + db * db) as u32
+}
+
+/// Find the index in the xterm 256-color palette nearest to an RGB color.
+///
+/// The nearest color in the 6×6×6 color cube and the nearest color in the grayscale ramp are compared using the squared euclidean distance, preferring the color cube when they're equally close. The system colors (0-15) are never returned, since they differ between terminals.
+pub fn rgb_to_xterm256(r: u8, g: u8, b: u8) -> u8 {
+    fn cube_index(value: u8) -> u8 {
+        match value {
+            0..=47 => 0,
+            48..=114 => 1,
+            _ => (value - 35) / 40,
+        }
+    }
+
+    let (ri, gi, bi) = (cube_index(r), cube_index(g), cube_index(b));
+    let cube = 16 + 36 * ri + 6 * gi + bi;
+
+    let average = (r as u16 + g as u16 + b as u16) / 3;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+
+    if distance((r, g, b), xterm256_to_rgb(gray)) < distance((r, g, b), xterm256_to_rgb(cube)) {
+        gray
+    } else {
+        cube
+    }
+}
+
+/// The amount of colors supported by a terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorLevel {
+    /// The 16 standard colors, see `Color`.
+    Ansi16,
+    /// The xterm 256-color palette, see `Color256`.
+    Ansi256,
+    /// 24-bit RGB colors, see `ColorRgb`.
+    TrueColor,
+}
+
+enum DowngradedColor {
+    Ansi16(Color),
+    Ansi256(Color256),
+    TrueColor(ColorRgb),
+}
+
+impl fmt::Display for DowngradedColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DowngradedColor::Ansi16(color) => color.fmt(f),
+            DowngradedColor::Ansi256(color) => color.fmt(f),
+            DowngradedColor::TrueColor(color) => color.fmt(f),
+        }
+    }
+}
+
+/// Set the foreground color of subsequent text to the color nearest to `rgb` that is supported at `level`.
+pub fn downgrade_fg(rgb: (u8, u8, u8), level: ColorLevel) -> impl fmt::Display {
+    const COLORS: [Color; 16] = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::White,
+        Color::BrightBlack,
+        Color::BrightRed,
+        Color::BrightGreen,
+        Color::BrightYellow,
+        Color::BrightBlue,
+        Color::BrightMagenta,
+        Color::BrightCyan,
+        Color::BrightWhite,
+    ];
+
+    match level {
+        ColorLevel::Ansi16 => {
+            let nearest = (0..16u8).min_by_key(|&index| distance(rgb, xterm256_to_rgb(index))).unwrap_or(0);
+            DowngradedColor::Ansi16(COLORS[nearest as usize])
+        }
+        ColorLevel::Ansi256 => DowngradedColor::Ansi256(Color256(rgb_to_xterm256(rgb.0, rgb.1, rgb.2))),
+        ColorLevel::TrueColor => DowngradedColor::TrueColor(ColorRgb::from(rgb)),
+    }
+}
+
+#[cfg(feature = "std")]
+fn color_level_from(colorterm: Option<&str>, term: Option<&str>) -> ColorLevel {
+    if let Some("truecolor") | Some("24bit") = colorterm {
+        return ColorLevel::TrueColor;
+    }
+
+    match term {
+        Some(term) if term.contains("256color") => ColorLevel::Ansi256,
+        _ => ColorLevel::Ansi16,
+    }
+}
+
+/// Detect the amount of colors supported by the terminal from the environment. `COLORTERM` set to `truecolor` or `24bit` gives `TrueColor`, a `TERM` containing `256color` gives `Ansi256`, and anything else gives `Ansi16`.
+///
+/// This doesn't check whether stdout is a terminal, since `std::io::IsTerminal` requires Rust 1.70, which is newer than the minimum supported Rust version of this crate. Callers that might be piped to a file should check that themselves and skip colors entirely.
+#[cfg(feature = "std")]
+pub fn detect_color_level() -> ColorLevel {
+    let colorterm = std::env::var("COLORTERM").ok();
+    let term = std::env::var("TERM").ok();
+
+    color_level_from(colorterm.as_deref(), term.as_deref())
+}
+
+escape_code!("Reset all text attributes and colors.", ResetStyle, "\x1B[0m");
+escape_code!("Make subsequent text bold.", Bold, "\x1B[1m");
+escape_code!("Make subsequent text dim.", Dim, "\x1B[2m");
+escape_code!("Make subsequent text italic.", Italic, "\x1B[3m");
+escape_code!("Make subsequent text underlined.", Underline, "\x1B[4m");
+escape_code!("Make subsequent text blink.", Blink, "\x1B[5m");
+escape_code!("Make subsequent text blink rapidly. Few terminals support this, and most treat it like `Blink`.", RapidBlink, "\x1B[6m");
+escape_code!("Swap the foreground and background colors of subsequent text.", Inverse, "\x1B[7m");
+escape_code!("Make subsequent text hidden.", Hidden, "\x1B[8m");
+escape_code!("Cross out subsequent text.", Strikethrough, "\x1B[9m");
+escape_code!("Conceal subsequent text, e.g. a password being typed. The same as `Hidden`.", Conceal, Hidden::SEQUENCE);
+
+escape_code!("Turn off bold text. Note that this also turns off dim text.", BoldOff, "\x1B[22m");
+escape_code!("Turn off dim text. Note that this also turns off bold text.", DimOff, "\x1B[22m");
+escape_code!("Turn off italic text.", ItalicOff, "\x1B[23m");
+escape_code!("Turn off underlined text.", UnderlineOff, "\x1B[24m");
+escape_code!("Turn off blinking text.", BlinkOff, "\x1B[25m");
+escape_code!("Turn off inverse text.", InverseOff, "\x1B[27m");
+escape_code!("Turn off hidden text.", HiddenOff, "\x1B[28m");
+escape_code!("Turn off crossed out text.", StrikethroughOff, "\x1B[29m");
+escape_code!("Reveal concealed text. The same as `HiddenOff`.", Reveal, HiddenOff::SEQUENCE);
+escape_code!("Turn off concealed text. The same as `HiddenOff` and `Reveal`.", ConcealOff, HiddenOff::SEQUENCE);
+
+escape_code!("Draw a line above subsequent text. Support for this varies between terminals.", Overline, "\x1B[53m");
+escape_code!("Turn off overlined text.", OverlineOff, "\x1B[55m");
+
+/// Switch subsequent text to an alternative font, where 0 is the primary font and 1 to 9 are the alternative fonts. Very few terminals support this. Use `new` to check that the index is in range, like `FillRectangle::new` and `Csi::new`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectFont(pub u8);
+
+impl SelectFont {
+    /// Returns `None` if `index` is above 9.
+    pub fn new(index: u8) -> Option<Self> {
+        if index > 9 {
+            return None;
+        }
+
+        Some(SelectFont(index))
+    }
+}
+
+impl fmt::Display for SelectFont {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}m", 10 + u16::from(self.0))
+    }
+}
+
+/// Set the style of the underline of subsequent text, as supported by e.g. kitty and VTE based terminals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnderlineStyle {
+    /// No underline, `\x1B[4:0m`.
+    None,
+    /// Straight underline, `\x1B[4:1m`.
+    Single,
+    /// Double underline, `\x1B[4:2m`.
+    Double,
+    /// Curly underline, `\x1B[4:3m`.
+    Curly,
+    /// Dotted underline, `\x1B[4:4m`.
+    Dotted,
+    /// Dashed underline, `\x1B[4:5m`.
+    Dashed,
+}
+
+impl fmt::Display for UnderlineStyle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = match *self {
+            UnderlineStyle::None => 0,
+            UnderlineStyle::Single => 1,
+            UnderlineStyle::Double => 2,
+            UnderlineStyle::Curly => 3,
+            UnderlineStyle::Dotted => 4,
+            UnderlineStyle::Dashed => 5,
+        };
+
+        write!(f, "\x1B[4:{}m", code)
+    }
+}
+
+/// Set the color of the underline of subsequent text to an index in the xterm 256-color palette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnderlineColor256(pub u8);
+
+impl fmt::Display for UnderlineColor256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[58;5;{}m", self.0)
+    }
+}
+
+/// Set the color of the underline of subsequent text to a 24-bit RGB color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnderlineColorRgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl fmt::Display for UnderlineColorRgb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[58;2;{};{};{}m", self.r, self.g, self.b)
+    }
+}
+
+escape_code!("Reset the color of the underline to the same as the text.", UnderlineColorReset, "\x1B[59m");
+
+/// Output text with the given styles applied, followed by `ResetStyle` if any style was applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StyledText<'a> {
+    pub text: &'a str,
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub underline: bool,
+}
+
+impl<'a> fmt::Display for StyledText<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(color) = self.color {
+            write!(f, "{}", color)?;
+        }
+
+        if self.bold {
+            write!(f, "{}", Bold)?;
+        }
+
+        if self.underline {
+            write!(f, "{}", Underline)?;
+        }
+
+        write!(f, "{}", self.text)?;
+
+        if self.color.is_some() || self.bold || self.underline {
+            write!(f, "{}", ResetStyle)?;
+        }
+
+        fmt::Result::Ok(())
+    }
+}
+
+/// Combine multiple text attributes and colors into a single escape code, e.g. `Sgr::new().bold().fg(Color::Red)` outputs `\x1B[1;31m`.
+///
+/// Up to 16 attributes can be combined, any further attributes are ignored. An empty `Sgr` outputs nothing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Sgr {
+    codes: [u8; 16],
+    len: usize,
+}
+
+// Serialized as the list of active codes, e.g. `[1,31]`, rather than the fixed size storage. Entries past `len` are always 0, so that the derived `PartialEq` and `Hash` only depend on the active codes.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Sgr {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.codes[..self.len].iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Sgr {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SgrVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for SgrVisitor {
+            type Value = Sgr;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a list of at most 16 SGR codes")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Sgr, A::Error> {
+                let mut sgr = Sgr::new();
+
+                while let Some(code) = seq.next_element::<u8>()? {
+                    if sgr.len == sgr.codes.len() {
+                        return Err(serde::de::Error::invalid_length(sgr.len + 1, &self));
+                    }
+
+                    sgr = sgr.push(code);
+                }
+
+                Ok(sgr)
+            }
+        }
+
+        deserializer.deserialize_seq(SgrVisitor)
+    }
+}
+
+impl Sgr {
+    pub fn new() -> Self {
+        Sgr { codes: [0; 16], len: 0 }
+    }
+
+    fn push(mut self, code: u8) -> Self {
+        if self.len < self.codes.len() {
+            self.codes[self.len] = code;
+            self.len += 1;
+        }
+
+        self
+    }
+
+    pub fn bold(self) -> Self {
+        self.push(1)
+    }
+
+    pub fn dim(self) -> Self {
+        self.push(2)
+    }
+
+    pub fn italic(self) -> Self {
+        self.push(3)
+    }
+
+    pub fn underline(self) -> Self {
+        self.push(4)
+    }
+
+    pub fn blink(self) -> Self {
+        self.push(5)
+    }
+
+    pub fn inverse(self) -> Self {
+        self.push(7)
+    }
+
+    pub fn hidden(self) -> Self {
+        self.push(8)
+    }
+
+    pub fn strikethrough(self) -> Self {
+        self.push(9)
+    }
+
+    pub fn fg(self, color: Color) -> Self {
+        self.push(color.code())
+    }
+
+    pub fn bg(self, color: BgColor) -> Self {
+        self.push(color.code())
+    }
+}
+
+impl Default for Sgr {
+    fn default() -> Self {
+        Sgr::new()
+    }
+}
+
+impl fmt::Display for Sgr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.len == 0 {
+            return fmt::Result::Ok(());
+        }
+
+        write!(f, "\x1B[")?;
+
+        for (idx, code) in self.codes[..self.len].iter().enumerate() {
+            if idx > 0 {
+                write!(f, ";")?;
+            }
+
+            write!(f, "{}", code)?;
+        }
+
+        write!(f, "m")
+    }
+}
+
+/// Output a [hyperlink](https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feda) using OSC 8.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Link<'a> {
+    pub url: &'a str,
+    pub text: &'a str,
+}
+
+impl<'a> fmt::Display for Link<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]8;;{}\x1B\\{}\x1B]8;;\x1B\\", self.url, self.text)
+    }
+}
+
+/// Output a hyperlink with an `id`, so that links sharing the same `id` and `url` are highlighted as one, e.g. when wrapped over multiple lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkWithId<'a> {
+    pub id: &'a str,
+    pub url: &'a str,
+    pub text: &'a str,
+}
+
+impl<'a> fmt::Display for LinkWithId<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]8;id={};{}\x07{}\x1B]8;;\x07", self.id, self.url, self.text)
+    }
+}
+
+/// Set both the icon name and the window title of the terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetTitle<'a>(pub &'a str);
+
+impl<'a> fmt::Display for SetTitle<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]0;{}\x07", self.0)
+    }
+}
+
+/// Set the icon name of the terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetIconName<'a>(pub &'a str);
+
+impl<'a> fmt::Display for SetIconName<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]1;{}\x07", self.0)
+    }
+}
+
+/// Set the window title of the terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetWindowTitle<'a>(pub &'a str);
+
+impl<'a> fmt::Display for SetWindowTitle<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]2;{}\x07", self.0)
+    }
+}
+
+struct PercentEncoded<'a>(&'a str);
+
+impl<'a> fmt::Display for PercentEncoded<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &byte in self.0.as_bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => write!(f, "{}", byte as char)?,
+                _ => write!(f, "%{:02X}", byte)?,
+            }
+        }
+
+        fmt::Result::Ok(())
+    }
+}
+
+/// Report the current working directory to the terminal using OSC 7. The path is percent-encoded as needed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetWorkingDirectory<'a> {
+    pub host: &'a str,
+    pub path: &'a str,
+}
+
+impl<'a> fmt::Display for SetWorkingDirectory<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]7;file://{}{}\x07", self.host, PercentEncoded(self.path))
+    }
+}
+
+/// Show a desktop notification using OSC 9, as supported by e.g. iTerm2 and Windows Terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Notify<'a>(pub &'a str);
+
+impl<'a> fmt::Display for Notify<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]9;{}\x07", self.0)
+    }
+}
+
+/// Show a desktop notification with a title using OSC 777, as supported by e.g. VTE based terminals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NotifyTitled<'a> {
+    pub title: &'a str,
+    pub body: &'a str,
+}
+
+impl<'a> fmt::Display for NotifyTitled<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]777;notify;{};{}\x07", self.title, self.body)
+    }
+}
+
+escape_code!(
+    "Mark the start of a shell prompt using OSC 133, for terminals with shell integration such as iTerm2 and WezTerm.",
+    PromptStart,
+    "\x1B]133;A\x07"
+);
+escape_code!(
+    "Mark the end of a shell prompt and the start of the command typed by the user, using OSC 133.",
+    CommandStart,
+    "\x1B]133;B\x07"
+);
+escape_code!("Mark the start of the output of a command, using OSC 133.", CommandExecuted, "\x1B]133;C\x07");
+
+/// Mark the end of the output of a command, and its exit code, using OSC 133.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CommandFinished(pub i32);
+
+impl fmt::Display for CommandFinished {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]133;D;{}\x07", self.0)
+    }
+}
+
+/// Manipulate or query the terminal window (XTWINOPS). Support for these varies between terminal emulators.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindowOp {
+    /// Resize the window to a specific amount of rows and columns.
+    Resize {
+        rows: u16,
+        cols: u16,
+    },
+    Maximize,
+    Unmaximize,
+    /// Report the size of the window in characters, the reply is `\x1B[8;{rows};{cols}t`.
+    ReportSizeChars,
+    /// Report the size of the window in pixels, the reply is `\x1B[4;{height};{width}t`.
+    ReportSizePixels,
+}
+
+impl fmt::Display for WindowOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WindowOp::Resize { rows, cols } => write!(f, "\x1B[8;{};{}t", rows, cols),
+            WindowOp::Maximize => write!(f, "\x1B[9;1t"),
+            WindowOp::Unmaximize => write!(f, "\x1B[9;0t"),
+            WindowOp::ReportSizeChars => write!(f, "\x1B[18t"),
+            WindowOp::ReportSizePixels => write!(f, "\x1B[14t"),
+        }
+    }
+}
+
+/// Set a color in the terminal palette using OSC 4.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetPaletteColor {
+    pub index: u8,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl fmt::Display for SetPaletteColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]4;{};rgb:{:02x}/{:02x}/{:02x}\x07", self.index, self.r, self.g, self.b)
+    }
+}
+
+/// Set the 16 standard colors of the terminal palette at once, using one `SetPaletteColor` for each index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Palette {
+    pub colors: [(u8, u8, u8); 16],
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, &(r, g, b)) in self.colors.iter().enumerate() {
+            write!(f, "{}", SetPaletteColor { index: index as u8, r, g, b })?;
+        }
+
+        fmt::Result::Ok(())
+    }
+}
+
+/// Reset a color in the terminal palette to its default using OSC 104.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResetPaletteColor(pub u8);
+
+impl fmt::Display for ResetPaletteColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]104;{}\x07", self.0)
+    }
+}
+
+escape_code!("Reset all colors in the terminal palette to their defaults.", ResetAllPaletteColors, "\x1B]104\x07");
+
+/// Set the default foreground color of the terminal using OSC 10.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetDefaultForeground {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl fmt::Display for SetDefaultForeground {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]10;rgb:{:02x}/{:02x}/{:02x}\x07", self.r, self.g, self.b)
+    }
+}
+
+/// Set the default background color of the terminal using OSC 11.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetDefaultBackground {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl fmt::Display for SetDefaultBackground {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]11;rgb:{:02x}/{:02x}/{:02x}\x07", self.r, self.g, self.b)
+    }
+}
+
+escape_code!("Reset the default foreground color of the terminal.", ResetDefaultForeground, "\x1B]110\x07");
+escape_code!("Reset the default background color of the terminal.", ResetDefaultBackground, "\x1B]111\x07");
+
+struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> fmt::Write for SliceWriter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+
+        if end > self.buf.len() {
+            return Err(fmt::Error);
+        }
+
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+
+        fmt::Result::Ok(())
+    }
+}
+
+/// Write escape codes into a fixed size byte buffer, without an allocator.
+pub trait EscapeBytes: fmt::Display {
+    /// Write the escape code into `buf` and return the number of bytes written. Fails if `buf` is too small.
+    fn write_bytes(&self, buf: &mut [u8]) -> Result<usize, fmt::Error> {
+        let mut writer = SliceWriter { buf, len: 0 };
+        fmt::write(&mut writer, format_args!("{}", self))?;
+        Ok(writer.len)
+    }
+}
+
+impl<T: fmt::Display + ?Sized> EscapeBytes for T {}
+
+/// A `fmt::Write` that only counts the number of bytes written to it, e.g. to size a buffer before writing escape codes into it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CountingSink {
+    pub bytes: usize,
+}
+
+impl fmt::Write for CountingSink {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.bytes += s.len();
+        fmt::Result::Ok(())
+    }
+}
+
+/// A fixed capacity buffer for building escape codes without an allocator, e.g. `write!(buf, "{}{}", CursorHide, Color::Red)`. Writes that don't fit in the remaining capacity fail with `fmt::Error` and leave the buffer unchanged.
+#[derive(Clone, Copy)]
+pub struct SequenceBuf<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> SequenceBuf<N> {
+    pub const fn new() -> Self {
+        SequenceBuf { buf: [0; N], len: 0 }
+    }
+
+    /// The bytes written so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// The text written so far.
+    pub fn as_str(&self) -> &str {
+        // Only whole `str`s are ever written to the buffer
+        core::str::from_utf8(self.as_bytes()).unwrap_or("")
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove everything written so far, keeping the capacity.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+// Only the written bytes are compared, whatever is left in the rest of the buffer is ignored
+impl<const N: usize> PartialEq for SequenceBuf<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl<const N: usize> Eq for SequenceBuf<N> {}
+
+impl<const N: usize> core::hash::Hash for SequenceBuf<N> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl<const N: usize> fmt::Debug for SequenceBuf<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SequenceBuf").field(&self.as_str()).finish()
+    }
+}
+
+impl<const N: usize> Default for SequenceBuf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Write for SequenceBuf<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut writer = SliceWriter { buf: &mut self.buf, len: self.len };
+        writer.write_str(s)?;
+        self.len = writer.len;
+        fmt::Result::Ok(())
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments) -> fmt::Result {
+        // Escape codes may be written in several pieces, so undo any partial write
+        let len = self.len;
+        let result = fmt::write(self, args);
+
+        if result.is_err() {
+            self.len = len;
+        }
+
+        result
+    }
+}
+
+impl<const N: usize> fmt::Display for SequenceBuf<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Write escape codes directly to an `std::io::Write`, without converting between `fmt` and `io` errors.
+#[cfg(feature = "std")]
+pub trait WriteAnsi: fmt::Display {
+    fn write_to<W: std::io::Write + ?Sized>(&self, w: &mut W) -> std::io::Result<()> {
+        write!(w, "{}", self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Display + ?Sized> WriteAnsi for T {}
+
+/// Writes `BeginSynchronizedUpdate` when created and `EndSynchronizedUpdate` when dropped. Writes to the guard are forwarded to the underlying writer.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct SyncGuard<W: std::io::Write> {
+    writer: W,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> SyncGuard<W> {
+    pub fn new(mut writer: W) -> std::io::Result<Self> {
+        write!(writer, "{}", BeginSynchronizedUpdate)?;
+        Ok(SyncGuard { writer })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> std::io::Write for SyncGuard<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Drop for SyncGuard<W> {
+    fn drop(&mut self) {
+        let _ = write!(self.writer, "{}", EndSynchronizedUpdate);
+        let _ = self.writer.flush();
+    }
+}
+
+/// Writes `EnterAlternativeScreen`, and optionally `CursorHide`, when created and `ExitAlternativeScreen`, and `CursorShow` if hidden, when dropped. This restores the terminal even if the program panics. Writes to the guard are forwarded to the underlying writer.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct AlternativeScreenGuard<W: std::io::Write> {
+    writer: W,
+    hide_cursor: bool,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> AlternativeScreenGuard<W> {
+    pub fn new(mut writer: W, hide_cursor: bool) -> std::io::Result<Self> {
+        write!(writer, "{}", EnterAlternativeScreen)?;
+
+        if hide_cursor {
+            write!(writer, "{}", CursorHide)?;
+        }
+
+        writer.flush()?;
+
+        Ok(AlternativeScreenGuard { writer, hide_cursor })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> std::io::Write for AlternativeScreenGuard<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Drop for AlternativeScreenGuard<W> {
+    fn drop(&mut self) {
+        let _ = write!(self.writer, "{}", ExitAlternativeScreen);
+
+        if self.hide_cursor {
+            let _ = write!(self.writer, "{}", CursorShow);
+        }
+
+        let _ = self.writer.flush();
+    }
+}
+
+#[cfg(feature = "std")]
+fn hyperlink_support_from(term: Option<&str>, term_program: Option<&str>, vte_version: Option<&str>, wt_session: bool) -> bool {
+    // Windows Terminal doesn't set `TERM` when running e.g. cmd or PowerShell
+    if wt_session {
+        return true;
+    }
+
+    if let Some("iTerm.app") | Some("WezTerm") | Some("vscode") | Some("ghostty") = term_program {
+        return true;
+    }
+
+    if let Some("dumb") = term {
+        return false;
+    }
+
+    if let Some(term) = term {
+        if term.contains("kitty") || term.contains("alacritty") || term.contains("foot") {
+            return true;
+        }
+    }
+
+    // VTE based terminals, e.g. GNOME Terminal, support hyperlinks since 0.50
+    if let Some(version) = vte_version.and_then(|version| version.parse::<u32>().ok()) {
+        return version >= 5000;
+    }
+
+    false
+}
+
+/// Guess whether the terminal supports hyperlinks from the environment, by looking at `TERM`, `TERM_PROGRAM`, `VTE_VERSION` and `WT_SESSION`. Unknown terminals are assumed not to support them.
+#[cfg(feature = "std")]
+pub fn detect_hyperlink_support() -> bool {
+    let term = std::env::var("TERM").ok();
+    let term_program = std::env::var("TERM_PROGRAM").ok();
+    let vte_version = std::env::var("VTE_VERSION").ok();
+    let wt_session = std::env::var_os("WT_SESSION").is_some();
+
+    hyperlink_support_from(term.as_deref(), term_program.as_deref(), vte_version.as_deref(), wt_session)
+}
+
+#[cfg(feature = "std")]
+enum LinkOrPlain<'a> {
+    Link(Link<'a>),
+    Plain(&'a str),
+}
+
+#[cfg(feature = "std")]
+impl<'a> fmt::Display for LinkOrPlain<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LinkOrPlain::Link(link) => link.fmt(f),
+            LinkOrPlain::Plain(text) => f.write_str(text),
+        }
+    }
+}
+
+/// Output a hyperlink if `detect_hyperlink_support` thinks the terminal supports it, otherwise output only `text`.
+#[cfg(feature = "std")]
+pub fn link_or_plain<'a>(url: &'a str, text: &'a str) -> impl fmt::Display + 'a {
+    if detect_hyperlink_support() {
+        LinkOrPlain::Link(Link { url, text })
+    } else {
+        LinkOrPlain::Plain(text)
+    }
+}
+
+/// Restore the terminal to a sane state, e.g. from a panic or `Ctrl-C` handler. Writes, in this order: `CursorShow`, `ExitAlternativeScreen`, `ResetStyle`, `DisableMouseTracking`, `DisableMouseButtonEvent`, `DisableMouseAnyEvent`, `DisableSgrMouse`, `DisableBracketedPaste` and `DisableFocusReporting`, then flushes the writer.
+#[cfg(feature = "std")]
+pub fn reset_terminal<W: std::io::Write>(w: &mut W) -> std::io::Result<()> {
+    write!(
+        w,
+        "{}{}{}{}{}{}{}{}{}",
+        CursorShow, ExitAlternativeScreen, ResetStyle, DisableMouseTracking, DisableMouseButtonEvent, DisableMouseAnyEvent, DisableSgrMouse, DisableBracketedPaste, DisableFocusReporting
+    )?;
+
+    w.flush()
+}
+
+#[cfg(feature = "std")]
+fn find_cursor_position(input: &[u8]) -> Option<(u16, u16)> {
+    for start in 0..input.len() {
+        if !input[start..].starts_with(b"\x1B[") {
+            continue;
+        }
+
+        let params = &input[start + 2..];
+        let len = params.iter().take_while(|&&byte| byte.is_ascii_digit() || byte == b';').count();
+
+        if params.get(len) == Some(&b'R') {
+            if let Some(position) = parse_cursor_position(&input[start..start + len + 3]) {
+                return Some(position);
+            }
+        }
+    }
+
+    None
+}
+
+/// Write `CursorGetPosition` to `out` and read the reply from `inp`, returning a 1-based `(row, col)`. Any other input read before the reply is discarded.
+///
+/// The terminal needs to be in raw mode, otherwise the reply won't be available until the user presses enter.
+#[cfg(feature = "std")]
+pub fn query_cursor_position<R: std::io::Read, W: std::io::Write>(out: &mut W, inp: &mut R) -> std::io::Result<(u16, u16)> {
+    write!(out, "{}", CursorGetPosition)?;
+    out.flush()?;
+
+    let mut input = std::vec::Vec::new();
+    let mut buf = [0u8; 64];
+
+    loop {
+        let len = match inp.read(&mut buf) {
+            Ok(0) => return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "no cursor position reply")),
+            Ok(len) => len,
+            Err(ref err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+
+        input.extend_from_slice(&buf[..len]);
+
+        if let Some(position) = find_cursor_position(&input) {
+            return Ok(position);
+        }
+    }
+}
+
+/// Concatenate multiple escape codes into a single value, without allocating. E.g. `Sequence::new().then(Bold).then(Color::Green)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sequence<T>(T);
+
+impl Sequence<()> {
+    pub fn new() -> Self {
+        Sequence(())
+    }
+}
+
+impl<T> Sequence<T> {
+    /// Append an escape code, or any other displayable value, to the end of the sequence.
+    pub fn then<U: fmt::Display>(self, next: U) -> Sequence<(Sequence<T>, U)> {
+        Sequence((self, next))
+    }
+}
+
+impl fmt::Display for Sequence<()> {
+    fn fmt(&self, _f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Result::Ok(())
+    }
+}
+
+impl<A: fmt::Display, B: fmt::Display> fmt::Display for Sequence<(A, B)> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", (self.0).0, (self.0).1)
+    }
+}
+
+/// Concatenate multiple escape codes, or any other displayable values, into a single `Sequence`. E.g. `ansi!(CursorHide, Color::Red, "text", ResetStyle)`.
+#[macro_export]
+macro_rules! ansi {
+    ($($item:expr),* $(,)?) => {
+        $crate::Sequence::new()$(.then($item))*
+    };
+}
+
+/// Display an image using [Sixel graphics](https://vt100.net/docs/vt3xx-gp/chapter14.html). `0` is the already encoded Sixel data, which is wrapped in a DCS introducer and terminator. Encoding an image to Sixel is out of scope for this crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sixel<'a>(pub &'a str);
+
+impl<'a> fmt::Display for Sixel<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1BP{}\x1B\\", self.0)
+    }
+}
+
+struct Base64<'a>(&'a [u8]);
+
+impl<'a> fmt::Display for Base64<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+        for chunk in self.0.chunks(3) {
+            let b0 = chunk[0] as usize;
+            let b1 = chunk.get(1).copied().unwrap_or(0) as usize;
+            let b2 = chunk.get(2).copied().unwrap_or(0) as usize;
+
+            let c0 = ALPHABET[b0 >> 2] as char;
+            let c1 = ALPHABET[((b0 & 0x03) << 4) | (b1 >> 4)] as char;
+            let c2 = if chunk.len() > 1 { ALPHABET[((b1 & 0x0F) << 2) | (b2 >> 6)] as char } else { '=' };
+            let c3 = if chunk.len() > 2 { ALPHABET[b2 & 0x3F] as char } else { '=' };
+
+            write!(f, "{}{}{}{}", c0, c1, c2, c3)?;
+        }
+
+        fmt::Result::Ok(())
+    }
+}
+
+/// A dimension of an image displayed with `ITermImage`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ImageDim {
+    Cells(u16),
+    Pixels(u16),
+    Percent(u16),
+}
+
+impl fmt::Display for ImageDim {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ImageDim::Cells(n) => write!(f, "{}", n),
+            ImageDim::Pixels(n) => write!(f, "{}px", n),
+            ImageDim::Percent(n) => write!(f, "{}%", n),
+        }
+    }
+}
+
+/// Display an image inline using the [iTerm2 image protocol](https://iterm2.com/documentation-images.html). `data` is the raw file contents, e.g. a PNG.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ITermImage<'a> {
+    pub data: &'a [u8],
+    pub width: Option<ImageDim>,
+    pub height: Option<ImageDim>,
+    pub preserve_aspect_ratio: bool,
+}
+
+impl<'a> fmt::Display for ITermImage<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B]1337;File=inline=1;size={}", self.data.len())?;
+
+        if let Some(width) = self.width {
+            write!(f, ";width={}", width)?;
+        }
+
+        if let Some(height) = self.height {
+            write!(f, ";height={}", height)?;
+        }
+
+        write!(f, ";preserveAspectRatio={}:{}\x07", self.preserve_aspect_ratio as u8, Base64(self.data))
+    }
+}
+
+/// The pixel format of the data passed to `KittyImage`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KittyFormat {
+    /// 24-bit RGB, 3 bytes per pixel, `f=24`.
+    Rgb,
+    /// 32-bit RGBA, 4 bytes per pixel, `f=32`.
+    Rgba,
+    /// A PNG file, `f=100`.
+    Png,
+}
+
+/// Display an image using the [Kitty graphics protocol](https://sw.kovidgoyal.net/kitty/graphics-protocol/). The data is base64 encoded and split up into chunks of at most 4096 bytes, each sent in its own escape code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KittyImage<'a> {
+    pub data: &'a [u8],
+    pub format: KittyFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl<'a> fmt::Display for KittyImage<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // 3072 bytes of input encode to exactly 4096 bytes of base64
+        const CHUNK_SIZE: usize = 3072;
+
+        let format = match self.format {
+            KittyFormat::Rgb => 24,
+            KittyFormat::Rgba => 32,
+            KittyFormat::Png => 100,
+        };
+
+        if self.data.is_empty() {
+            return write!(f, "\x1B_Ga=T,f={},s={},v={},m=0;\x1B\\", format, self.width, self.height);
+        }
+
+        let count = (self.data.len() + CHUNK_SIZE - 1) / CHUNK_SIZE;
+
+        for (index, chunk) in self.data.chunks(CHUNK_SIZE).enumerate() {
+            let more = (index + 1 < count) as u8;
+
+            if index == 0 {
+                write!(f, "\x1B_Ga=T,f={},s={},v={},m={};{}\x1B\\", format, self.width, self.height, more, Base64(chunk))?;
+            } else {
+                write!(f, "\x1B_Gm={};{}\x1B\\", more, Base64(chunk))?;
+            }
+        }
+
+        fmt::Result::Ok(())
+    }
+}
+
+/// A selection that can be written to with `SetSelection`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClipboardSelection {
+    /// The system clipboard, `c`.
+    Clipboard,
+    /// The primary selection, `p`.
+    Primary,
+    /// The secondary selection, `s`.
+    Secondary,
+}
+
+/// Write text to a specific selection using OSC 52.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetSelection<'a> {
+    pub selection: ClipboardSelection,
+    pub text: &'a str,
+}
+
+impl<'a> fmt::Display for SetSelection<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let selection = match self.selection {
+            ClipboardSelection::Clipboard => 'c',
+            ClipboardSelection::Primary => 'p',
+            ClipboardSelection::Secondary => 's',
+        };
+
+        write!(f, "\x1B]52;{};{}\x07", selection, Base64(self.text.as_bytes()))
+    }
+}
+
+/// Write text to the system clipboard using OSC 52. This works over SSH as well.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetClipboard<'a>(pub &'a str);
+
+impl<'a> fmt::Display for SetClipboard<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        SetSelection {
+            selection: ClipboardSelection::Clipboard,
+            text: self.0,
+        }
+        .fmt(f)
+    }
+}
+
+fn parse_param(input: &[u8], default: u16) -> Option<u16> {
+    if input.is_empty() {
+        return Some(default);
+    }
+
+    let mut value: u16 = 0;
+
+    for &byte in input {
+        if !byte.is_ascii_digit() {
+            return None;
+        }
+
+        value = value.checked_mul(10)?.checked_add((byte - b'0') as u16)?;
+    }
+
+    Some(value)
+}
+
+/// Parse the reply to `CursorGetPosition`, e.g. `\x1B[12;34R`, into a 1-based `(row, col)`. A missing row or column defaults to 1.
+pub fn parse_cursor_position(input: &[u8]) -> Option<(u16, u16)> {
+    let params = input.strip_prefix(b"\x1B[")?.strip_suffix(b"R")?;
+    let mut parts = params.split(|&byte| byte == b';');
+
+    let row = parse_param(parts.next().unwrap_or(b""), 1)?;
+    let col = parse_param(parts.next().unwrap_or(b""), 1)?;
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some((row, col))
+}
+
+/// Parse the reply to `QueryDecMode`, e.g. `\x1B[?2026;1$y`, into `(mode, state)`. The state is 0 if the mode isn't recognized, 1 if set, 2 if reset, 3 if permanently set and 4 if permanently reset.
+pub fn parse_dec_mode_reply(input: &[u8]) -> Option<(u16, u8)> {
+    let params = input.strip_prefix(b"\x1B[?")?.strip_suffix(b"$y")?;
+    let mut parts = params.split(|&byte| byte == b';');
+
+    let mode = parts.next().filter(|part| !part.is_empty())?;
+    let state = parts.next().filter(|part| !part.is_empty())?;
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let state = parse_param(state, 0)?;
+
+    if state > u8::MAX as u16 {
+        return None;
+    }
+
+    Some((parse_param(mode, 0)?, state as u8))
+}
+
+/// Parse a focus event sent after `EnableFocusReporting`, returning `true` when the terminal gained focus and `false` when it lost focus.
+pub fn parse_focus_event(input: &[u8]) -> Option<bool> {
+    match input {
+        b"\x1B[I" => Some(true),
+        b"\x1B[O" => Some(false),
+        _ => None,
+    }
+}
+
+fn skip_escape(bytes: &[u8], start: usize) -> usize {
+    let mut idx = start + 1;
+
+    match bytes.get(idx) {
+        None => idx,
+        Some(b'[') => {
+            idx += 1;
+
+            while let Some(&byte) = bytes.get(idx) {
+                match byte {
+                    0x20..=0x3F => idx += 1,
+                    0x40..=0x7E => return idx + 1,
+                    _ => return idx,
+                }
+            }
+
+            idx
+        }
+        Some(b']') | Some(b'P') | Some(b'X') | Some(b'^') | Some(b'_') => {
+            idx += 1;
+
+            while let Some(&byte) = bytes.get(idx) {
+                match byte {
+                    0x07 => return idx + 1,
+                    0x1B if bytes.get(idx + 1) == Some(&b'\\') => return idx + 2,
+                    _ => idx += 1,
+                }
+            }
+
+            idx
+        }
+        Some(_) => {
+            while let Some(0x20..=0x2F) = bytes.get(idx) {
+                idx += 1;
+            }
+
+            match bytes.get(idx) {
+                Some(0x30..=0x7E) => idx + 1,
+                _ => idx,
+            }
+        }
+    }
+}
+
+fn for_each_plain_text<'a>(input: &'a str, mut f: impl FnMut(&'a str)) {
+    let bytes = input.as_bytes();
+    let mut start = 0;
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        if bytes[idx] != 0x1B {
+            idx += 1;
+            continue;
+        }
+
+        if start < idx {
+            f(&input[start..idx]);
+        }
+
+        idx = skip_escape(bytes, idx);
+        start = idx;
+    }
+
+    if start < bytes.len() {
+        f(&input[start..]);
+    }
+}
+
+/// Remove all escape sequences from `input`, leaving only the plain text. Unterminated escape sequences at the end of `input` are dropped.
+#[cfg(feature = "alloc")]
+pub fn strip_ansi(input: &str) -> alloc::string::String {
+    let mut result = alloc::string::String::with_capacity(input.len());
+    for_each_plain_text(input, |text| result.push_str(text));
+    result
+}
+
+/// Render an escape code to an owned string. This is the same as `ToString::to_string`, but doesn't require importing the trait.
+#[cfg(feature = "alloc")]
+pub fn render<T: fmt::Display>(code: T) -> alloc::string::String {
+    alloc::string::ToString::to_string(&code)
+}
+
+/// Compute the escape codes that turn a screen showing `prev` into one showing `next`, where each item is one row starting at the top of the screen. Only the changed part of each row is rewritten, moving there with `CursorTo` and clearing leftover characters with `EraseEndLine`. Rows in `prev` without a counterpart in `next` are erased.
+///
+/// Every `char` is counted as one column, and `prev` and `next` must not contain escape codes. Changes past row or column 65536 can't be addressed with `CursorTo` and are skipped. The cursor is left after the last written change.
+#[cfg(feature = "alloc")]
+pub fn diff_render(prev: &[&str], next: &[&str]) -> alloc::string::String {
+    use core::fmt::Write;
+
+    let mut result = alloc::string::String::new();
+
+    for row in 0..prev.len().max(next.len()) {
+        let y = match u16::try_from(row) {
+            Ok(y) => y,
+            Err(_) => break,
+        };
+
+        let old = prev.get(row).copied().unwrap_or("");
+        let new = next.get(row).copied().unwrap_or("");
+
+        if old == new {
+            continue;
+        }
+
+        let old_len = old.chars().count();
+        let new_len = new.chars().count();
+        let prefix = old.chars().zip(new.chars()).take_while(|(a, b)| a == b).count();
+
+        // Rows of the same length only need the differing run in the middle rewritten
+        let suffix = if old_len == new_len {
+            old.chars().rev().zip(new.chars().rev()).take(new_len - prefix).take_while(|(a, b)| a == b).count()
+        } else {
+            0
+        };
+
+        let x = match u16::try_from(prefix) {
+            Ok(x) => x,
+            Err(_) => continue,
+        };
+
+        let _ = write!(result, "{}", CursorTo::AbsoluteXY(x, y));
+        result.extend(new.chars().skip(prefix).take(new_len - prefix - suffix));
+
+        if old_len > new_len {
+            let _ = write!(result, "{}", EraseEndLine);
+        }
+    }
+
+    result
+}
+
+/// Count the visible characters in `input`, skipping all escape sequences. Every `char` is currently counted as width 1, wide and combining characters are not yet handled.
+pub fn display_width(input: &str) -> usize {
+    let mut width = 0;
+    for_each_plain_text(input, |text| width += text.chars().count());
+    width
+}
+
+/// Output an arbitrary CSI sequence, for codes not otherwise covered by this crate. E.g. `Csi::new("38:2:255:0:0", "", b'm')`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Csi<'a> {
+    pub params: &'a str,
+    pub intermediate: &'a str,
+    pub final_byte: u8,
+}
+
+impl<'a> Csi<'a> {
+    /// Returns `None` if `final_byte` isn't in the range `0x40..=0x7E`.
+    pub fn new(params: &'a str, intermediate: &'a str, final_byte: u8) -> Option<Self> {
+        match final_byte {
+            0x40..=0x7E => Some(Csi { params, intermediate, final_byte }),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> fmt::Display for Csi<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\x1B[{}{}{}", self.params, self.intermediate, self.final_byte as char)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        string::{String, ToString},
+        vec::Vec,
+    };
+
+    macro_rules! assert_escape_output {
+        ($name:ident, $code:expr, $expected:expr) => {
+            #[test]
+            fn $name() {
+                let mut buf = Vec::new();
+                write!(buf, "{}", $code).unwrap();
+
+                let result = String::from_utf8(buf).unwrap();
+                assert_eq!(result, $expected);
+            }
+        };
+    }
+
+    assert_escape_output!(cursor_up_1, super::CursorUp(1), "\x1B[1A");
+    assert_escape_output!(cursor_up_23, super::CursorUp(23), "\x1B[23A");
+
+    assert_escape_output!(cursor_down_1, super::CursorDown(1), "\x1B[1B");
+    assert_escape_output!(cursor_down_23, super::CursorDown(23), "\x1B[23B");
+
+    assert_escape_output!(cursor_forward_1, super::CursorForward(1), "\x1B[1C");
+    assert_escape_output!(cursor_forward_23, super::CursorForward(23), "\x1B[23C");
+
+    assert_escape_output!(cursor_backward_1, super::CursorBackward(1), "\x1B[1D");
+    assert_escape_output!(cursor_backward_23, super::CursorBackward(23), "\x1B[23D");
+
+    assert_escape_output!(cursor_left, super::CursorLeft, "\x1B[1000D");
+    assert_escape_output!(cursor_save_position, super::CursorSavePosition, "\x1B[s");
+    assert_escape_output!(cursor_restore_position, super::CursorRestorePosition, "\x1B[u");
+    assert_escape_output!(cursor_get_position, super::CursorGetPosition, "\x1B[6n");
+    assert_escape_output!(cursor_next_line, super::CursorNextLine, "\x1B[E");
+    assert_escape_output!(cursor_prev_line, super::CursorPrevLine, "\x1B[F");
+    assert_escape_output!(cursor_hide, super::CursorHide, "\x1B[?25l");
+    assert_escape_output!(cursor_show, super::CursorShow, "\x1B[?25h");
+
+    assert_escape_output!(erase_lines_0, super::EraseLines(0), "");
+    assert_escape_output!(erase_lines_1, super::EraseLines(1), "\x1B[1000D\x1B[K");
+    assert_escape_output!(erase_lines_2, super::EraseLines(2), "\x1B[1000D\x1B[K\x1B[1A\x1B[1000D\x1B[K");
+    assert_escape_output!(erase_lines_3, super::EraseLines(3), "\x1B[1000D\x1B[K\x1B[1A\x1B[1000D\x1B[K\x1B[1A\x1B[1000D\x1B[K");
+
+    assert_escape_output!(color_black, super::Color::Black, "\x1B[30m");
+    assert_escape_output!(color_red, super::Color::Red, "\x1B[31m");
+    assert_escape_output!(color_green, super::Color::Green, "\x1B[32m");
+    assert_escape_output!(color_yellow, super::Color::Yellow, "\x1B[33m");
+    assert_escape_output!(color_blue, super::Color::Blue, "\x1B[34m");
+    assert_escape_output!(color_magenta, super::Color::Magenta, "\x1B[35m");
+    assert_escape_output!(color_cyan, super::Color::Cyan, "\x1B[36m");
+    assert_escape_output!(color_white, super::Color::White, "\x1B[37m");
+    assert_escape_output!(color_bright_black, super::Color::BrightBlack, "\x1B[90m");
+    assert_escape_output!(color_bright_red, super::Color::BrightRed, "\x1B[91m");
+    assert_escape_output!(color_bright_green, super::Color::BrightGreen, "\x1B[92m");
+    assert_escape_output!(color_bright_yellow, super::Color::BrightYellow, "\x1B[93m");
+    assert_escape_output!(color_bright_blue, super::Color::BrightBlue, "\x1B[94m");
+    assert_escape_output!(color_bright_magenta, super::Color::BrightMagenta, "\x1B[95m");
+    assert_escape_output!(color_bright_cyan, super::Color::BrightCyan, "\x1B[96m");
+    assert_escape_output!(color_bright_white, super::Color::BrightWhite, "\x1B[97m");
+    assert_escape_output!(color_reset, super::ColorReset, "\x1B[39m");
+
+    assert_escape_output!(bg_color_black, super::BgColor::Black, "\x1B[40m");
+    assert_escape_output!(bg_color_red, super::BgColor::Red, "\x1B[41m");
+    assert_escape_output!(bg_color_green, super::BgColor::Green, "\x1B[42m");
+    assert_escape_output!(bg_color_yellow, super::BgColor::Yellow, "\x1B[43m");
+    assert_escape_output!(bg_color_blue, super::BgColor::Blue, "\x1B[44m");
+    assert_escape_output!(bg_color_magenta, super::BgColor::Magenta, "\x1B[45m");
+    assert_escape_output!(bg_color_cyan, super::BgColor::Cyan, "\x1B[46m");
+    assert_escape_output!(bg_color_white, super::BgColor::White, "\x1B[47m");
+    assert_escape_output!(bg_color_bright_black, super::BgColor::BrightBlack, "\x1B[100m");
+    assert_escape_output!(bg_color_bright_red, super::BgColor::BrightRed, "\x1B[101m");
+    assert_escape_output!(bg_color_bright_green, super::BgColor::BrightGreen, "\x1B[102m");
+    assert_escape_output!(bg_color_bright_yellow, super::BgColor::BrightYellow, "\x1B[103m");
+    assert_escape_output!(bg_color_bright_blue, super::BgColor::BrightBlue, "\x1B[104m");
+    assert_escape_output!(bg_color_bright_magenta, super::BgColor::BrightMagenta, "\x1B[105m");
+    assert_escape_output!(bg_color_bright_cyan, super::BgColor::BrightCyan, "\x1B[106m");
+    assert_escape_output!(bg_color_bright_white, super::BgColor::BrightWhite, "\x1B[107m");
+    assert_escape_output!(bg_color_reset, super::BgColorReset, "\x1B[49m");
+
+    assert_escape_output!(color_256_0, super::Color256(0), "\x1B[38;5;0m");
+    assert_escape_output!(color_256_15, super::Color256(15), "\x1B[38;5;15m");
+    assert_escape_output!(color_256_231, super::Color256(231), "\x1B[38;5;231m");
+    assert_escape_output!(color_256_255, super::Color256(255), "\x1B[38;5;255m");
+
+    assert_escape_output!(bg_color_256_0, super::BgColor256(0), "\x1B[48;5;0m");
+    assert_escape_output!(bg_color_256_15, super::BgColor256(15), "\x1B[48;5;15m");
+    assert_escape_output!(bg_color_256_231, super::BgColor256(231), "\x1B[48;5;231m");
+    assert_escape_output!(bg_color_256_255, super::BgColor256(255), "\x1B[48;5;255m");
+
+    assert_escape_output!(color_rgb_red, super::ColorRgb { r: 255, g: 0, b: 0 }, "\x1B[38;2;255;0;0m");
+    assert_escape_output!(color_rgb_gray, super::ColorRgb::new(128, 128, 128), "\x1B[38;2;128;128;128m");
+    assert_escape_output!(color_rgb_black, super::ColorRgb::new(0, 0, 0), "\x1B[38;2;0;0;0m");
+
+    assert_escape_output!(bg_color_rgb_red, super::BgColorRgb { r: 255, g: 0, b: 0 }, "\x1B[48;2;255;0;0m");
+    assert_escape_output!(bg_color_rgb_gray, super::BgColorRgb::new(128, 128, 128), "\x1B[48;2;128;128;128m");
+    assert_escape_output!(bg_color_rgb_black, super::BgColorRgb::new(0, 0, 0), "\x1B[48;2;0;0;0m");
+
+    assert_escape_output!(reset_style, super::ResetStyle, "\x1B[0m");
+    assert_escape_output!(bold, super::Bold, "\x1B[1m");
+    assert_escape_output!(dim, super::Dim, "\x1B[2m");
+    assert_escape_output!(italic, super::Italic, "\x1B[3m");
+    assert_escape_output!(underline, super::Underline, "\x1B[4m");
+    assert_escape_output!(blink, super::Blink, "\x1B[5m");
+    assert_escape_output!(inverse, super::Inverse, "\x1B[7m");
+    assert_escape_output!(hidden, super::Hidden, "\x1B[8m");
+    assert_escape_output!(strikethrough, super::Strikethrough, "\x1B[9m");
+
+    assert_escape_output!(bold_off, super::BoldOff, "\x1B[22m");
+    assert_escape_output!(dim_off, super::DimOff, "\x1B[22m");
+    assert_escape_output!(italic_off, super::ItalicOff, "\x1B[23m");
+    assert_escape_output!(underline_off, super::UnderlineOff, "\x1B[24m");
+    assert_escape_output!(blink_off, super::BlinkOff, "\x1B[25m");
+    assert_escape_output!(inverse_off, super::InverseOff, "\x1B[27m");
+    assert_escape_output!(hidden_off, super::HiddenOff, "\x1B[28m");
+    assert_escape_output!(strikethrough_off, super::StrikethroughOff, "\x1B[29m");
+
+    assert_escape_output!(
+        link,
+        super::Link {
+            url: "https://example.com",
+            text: "click me"
+        },
+        "\x1B]8;;https://example.com\x1B\\click me\x1B]8;;\x1B\\"
+    );
+    assert_escape_output!(link_empty_text, super::Link { url: "https://example.com", text: "" }, "\x1B]8;;https://example.com\x1B\\\x1B]8;;\x1B\\");
+
+    assert_escape_output!(set_title, super::SetTitle("Hello"), "\x1B]0;Hello\x07");
+    assert_escape_output!(set_title_empty, super::SetTitle(""), "\x1B]0;\x07");
+    assert_escape_output!(set_icon_name, super::SetIconName("Hello"), "\x1B]1;Hello\x07");
+    assert_escape_output!(set_icon_name_empty, super::SetIconName(""), "\x1B]1;\x07");
+    assert_escape_output!(set_window_title, super::SetWindowTitle("Hello"), "\x1B]2;Hello\x07");
+    assert_escape_output!(set_window_title_empty, super::SetWindowTitle(""), "\x1B]2;\x07");
+
+    assert_escape_output!(cursor_shape_block_blink, super::CursorShape::BlockBlink, "\x1B[1 q");
+    assert_escape_output!(cursor_shape_block_steady, super::CursorShape::BlockSteady, "\x1B[2 q");
+    assert_escape_output!(cursor_shape_underline_blink, super::CursorShape::UnderlineBlink, "\x1B[3 q");
+    assert_escape_output!(cursor_shape_underline_steady, super::CursorShape::UnderlineSteady, "\x1B[4 q");
+    assert_escape_output!(cursor_shape_bar_blink, super::CursorShape::BarBlink, "\x1B[5 q");
+    assert_escape_output!(cursor_shape_bar_steady, super::CursorShape::BarSteady, "\x1B[6 q");
+
+    assert_escape_output!(set_scroll_region_full_screen, super::SetScrollRegion { top: 0, bottom: 23 }, "\x1B[1;24r");
+    assert_escape_output!(set_scroll_region_bottom_two_lines, super::SetScrollRegion { top: 22, bottom: 23 }, "\x1B[23;24r");
+    assert_escape_output!(set_scroll_region_max, super::SetScrollRegion { top: 0, bottom: u16::MAX }, "\x1B[1;65536r");
+    assert_escape_output!(reset_scroll_region, super::ResetScrollRegion, "\x1B[r");
+
+    assert_escape_output!(cursor_next_line_by_1, super::CursorNextLineBy(1), "\x1B[1E");
+    assert_escape_output!(cursor_next_line_by_5, super::CursorNextLineBy(5), "\x1B[5E");
+
+    assert_escape_output!(cursor_prev_line_by_1, super::CursorPrevLineBy(1), "\x1B[1F");
+    assert_escape_output!(cursor_prev_line_by_5, super::CursorPrevLineBy(5), "\x1B[5F");
+
+    assert_escape_output!(cursor_column_1, super::CursorColumn(1), "\x1B[1G");
+    assert_escape_output!(cursor_column_80, super::CursorColumn(80), "\x1B[80G");
+
+    assert_escape_output!(cursor_row_1, super::CursorRow(1), "\x1B[1d");
+    assert_escape_output!(cursor_row_80, super::CursorRow(80), "\x1B[80d");
+
+    assert_escape_output!(enable_bracketed_paste, super::EnableBracketedPaste, "\x1B[?2004h");
+    assert_escape_output!(disable_bracketed_paste, super::DisableBracketedPaste, "\x1B[?2004l");
+
+    assert_escape_output!(enable_mouse_tracking, super::EnableMouseTracking, "\x1B[?1000h");
+    assert_escape_output!(disable_mouse_tracking, super::DisableMouseTracking, "\x1B[?1000l");
+    assert_escape_output!(enable_mouse_button_event, super::EnableMouseButtonEvent, "\x1B[?1002h");
+    assert_escape_output!(disable_mouse_button_event, super::DisableMouseButtonEvent, "\x1B[?1002l");
+    assert_escape_output!(enable_mouse_any_event, super::EnableMouseAnyEvent, "\x1B[?1003h");
+    assert_escape_output!(disable_mouse_any_event, super::DisableMouseAnyEvent, "\x1B[?1003l");
+    assert_escape_output!(enable_sgr_mouse, super::EnableSgrMouse, "\x1B[?1006h");
+    assert_escape_output!(disable_sgr_mouse, super::DisableSgrMouse, "\x1B[?1006l");
+
+    assert_escape_output!(erase_chars_1, super::EraseChars(1), "\x1B[1X");
+    assert_escape_output!(erase_chars_10, super::EraseChars(10), "\x1B[10X");
+
+    assert_escape_output!(insert_lines_1, super::InsertLines(1), "\x1B[1L");
+    assert_escape_output!(insert_lines_3, super::InsertLines(3), "\x1B[3L");
+
+    assert_escape_output!(delete_lines_1, super::DeleteLines(1), "\x1B[1M");
+    assert_escape_output!(delete_lines_3, super::DeleteLines(3), "\x1B[3M");
+
+    assert_escape_output!(insert_chars_1, super::InsertChars(1), "\x1B[1@");
+    assert_escape_output!(insert_chars_3, super::InsertChars(3), "\x1B[3@");
+
+    assert_escape_output!(delete_chars_1, super::DeleteChars(1), "\x1B[1P");
+    assert_escape_output!(delete_chars_3, super::DeleteChars(3), "\x1B[3P");
+
+    #[test]
+    fn write_bytes_cursor_up_1() {
+        use super::EscapeBytes;
+
+        let mut buf = [0u8; 16];
+        let len = super::CursorUp(1).write_bytes(&mut buf).unwrap();
+
+        assert_eq!(len, 4);
+        assert_eq!(&buf[..len], b"\x1B[1A");
+    }
+
+    #[test]
+    fn write_bytes_erase_screen() {
+        use super::EscapeBytes;
+
+        let mut buf = [0u8; 16];
+        let len = super::EraseScreen.write_bytes(&mut buf).unwrap();
+
+        assert_eq!(len, 4);
+        assert_eq!(&buf[..len], b"\x1B[2J");
+    }
+
+    #[test]
+    fn write_bytes_too_small() {
+        use super::EscapeBytes;
+
+        let mut buf = [0u8; 3];
+        assert!(super::EraseScreen.write_bytes(&mut buf).is_err());
+    }
+
+    #[test]
+    fn clone_and_compare() {
+        let cursor = super::CursorTo::AbsoluteXY(3, 4);
+        let color = super::Color256(200);
+
+        assert_eq!(cursor.clone(), super::CursorTo::AbsoluteXY(3, 4));
+        assert_ne!(cursor, super::CursorTo::AbsoluteXY(4, 3));
+        assert_eq!(color.clone(), super::Color256(200));
+        assert_ne!(color, super::Color256(201));
+    }
+
+    assert_escape_output!(sequence_empty, super::Sequence::new(), "");
+    assert_escape_output!(sequence_single, super::Sequence::new().then(super::CursorHide), "\x1B[?25l");
+    assert_escape_output!(sequence_bold_green, super::Sequence::new().then(super::Bold).then(super::Color::Green), "\x1B[1m\x1B[32m");
+    assert_escape_output!(
+        sequence_mixed,
+        super::Sequence::new().then(super::CursorHide).then(super::Color::Red).then("text").then(super::ResetStyle),
+        "\x1B[?25l\x1B[31mtext\x1B[0m"
+    );
+
+    assert_escape_output!(base64_padding_2, super::Base64(b"h"), "aA==");
+    assert_escape_output!(base64_padding_1, super::Base64(b"hi"), "aGk=");
+    assert_escape_output!(base64_no_padding, super::Base64(b"hello!"), "aGVsbG8h");
+
+    assert_escape_output!(
+        iterm_image,
+        super::ITermImage {
+            data: b"hi",
+            width: None,
+            height: None,
+            preserve_aspect_ratio: true
+        },
+        "\x1B]1337;File=inline=1;size=2;preserveAspectRatio=1:aGk=\x07"
+    );
+    assert_escape_output!(
+        iterm_image_dimensions,
+        super::ITermImage {
+            data: b"hello",
+            width: Some(super::ImageDim::Cells(10)),
+            height: Some(super::ImageDim::Pixels(20)),
+            preserve_aspect_ratio: false
+        },
+        "\x1B]1337;File=inline=1;size=5;width=10;height=20px;preserveAspectRatio=0:aGVsbG8=\x07"
+    );
+    assert_escape_output!(image_dim_percent, super::ImageDim::Percent(50), "50%");
+
+    assert_escape_output!(set_clipboard, super::SetClipboard("hello"), "\x1B]52;c;aGVsbG8=\x07");
+    assert_escape_output!(
+        set_selection_primary,
+        super::SetSelection {
+            selection: super::ClipboardSelection::Primary,
+            text: "hello"
+        },
+        "\x1B]52;p;aGVsbG8=\x07"
+    );
+    assert_escape_output!(
+        set_selection_secondary,
+        super::SetSelection {
+            selection: super::ClipboardSelection::Secondary,
+            text: "hello"
+        },
+        "\x1B]52;s;aGVsbG8=\x07"
+    );
+
+    assert_escape_output!(
+        styled_text_no_style,
+        super::StyledText {
+            text: "plain",
+            color: None,
+            bold: false,
+            underline: false
+        },
+        "plain"
+    );
+    assert_escape_output!(
+        styled_text_color,
+        super::StyledText {
+            text: "ERROR",
+            color: Some(super::Color::Red),
+            bold: false,
+            underline: false
+        },
+        "\x1B[31mERROR\x1B[0m"
+    );
+    assert_escape_output!(
+        styled_text_multiple,
+        super::StyledText {
+            text: "ERROR",
+            color: Some(super::Color::Red),
+            bold: true,
+            underline: true
+        },
+        "\x1B[31m\x1B[1m\x1B[4mERROR\x1B[0m"
+    );
+
+    assert_escape_output!(cursor_move_x_0, super::CursorMove::X(0), "");
+    assert_escape_output!(cursor_move_y_0, super::CursorMove::Y(0), "");
+    assert_escape_output!(cursor_move_xy_0_5, super::CursorMove::XY(0, 5), "\x1B[5B");
+
+    #[test]
+    fn parse_cursor_position() {
+        assert_eq!(super::parse_cursor_position(b"\x1B[12;34R"), Some((12, 34)));
+        assert_eq!(super::parse_cursor_position(b"\x1B[R"), Some((1, 1)));
+        assert_eq!(super::parse_cursor_position(b"\x1B[;34R"), Some((1, 34)));
+        assert_eq!(super::parse_cursor_position(b"\x1B[12R"), Some((12, 1)));
+    }
+
+    #[test]
+    fn parse_cursor_position_malformed() {
+        assert_eq!(super::parse_cursor_position(b""), None);
+        assert_eq!(super::parse_cursor_position(b"\x1B[12;34"), None);
+        assert_eq!(super::parse_cursor_position(b"12;34R"), None);
+        assert_eq!(super::parse_cursor_position(b"\x1B[1a;34R"), None);
+        assert_eq!(super::parse_cursor_position(b"\x1B[1;2;3R"), None);
+        assert_eq!(super::parse_cursor_position(b"\x1B[99999;1R"), None);
+    }
+
+    assert_escape_output!(carriage_return, super::CarriageReturn, "\r");
+
+    assert_escape_output!(cursor_save_position_dec, super::CursorSavePositionDec, "\x1B7");
+    assert_escape_output!(cursor_restore_position_dec, super::CursorRestorePositionDec, "\x1B8");
+
+    #[test]
+    fn cursor_to_function() {
+        assert_eq!(super::cursor_to(3, 4).to_string(), super::CursorTo::AbsoluteXY(3, 4).to_string());
+    }
+
+    #[test]
+    fn cursor_move_function() {
+        assert_eq!(super::cursor_move(-3, 4).to_string(), super::CursorMove::XY(-3, 4).to_string());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn write_to_cursor_up_3() {
+        use super::WriteAnsi;
+
+        let mut buf = Vec::new();
+        super::CursorUp(3).write_to(&mut buf).unwrap();
+
+        assert_eq!(buf, b"\x1B[3A");
+    }
+
+    assert_escape_output!(scroll_up_by_1, super::ScrollUpBy(1), "\x1B[1S");
+    assert_escape_output!(scroll_up_by_10, super::ScrollUpBy(10), "\x1B[10S");
+
+    assert_escape_output!(scroll_down_by_1, super::ScrollDownBy(1), "\x1B[1T");
+    assert_escape_output!(scroll_down_by_10, super::ScrollDownBy(10), "\x1B[10T");
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn strip_ansi_color() {
+        let input = std::format!("{}red{} text", super::Color::Red, super::ColorReset);
+        assert_eq!(super::strip_ansi(&input), "red text");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn strip_ansi_link() {
+        let input = std::format!(
+            "see {}",
+            super::Link {
+                url: "https://example.com",
+                text: "here"
+            }
+        );
+        assert_eq!(super::strip_ansi(&input), "see here");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn strip_ansi_cursor_up() {
+        let input = std::format!("a{}b{}c", super::CursorUp(3), super::CursorSavePositionDec);
+        assert_eq!(super::strip_ansi(&input), "abc");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn strip_ansi_unterminated() {
+        assert_eq!(super::strip_ansi("text\x1B"), "text");
+        assert_eq!(super::strip_ansi("text\x1B[12;3"), "text");
+        assert_eq!(super::strip_ansi("text\x1B]0;title"), "text");
+        assert_eq!(super::strip_ansi("\x1B[1mb\u{f6}ld\x1B[0m"), "b\u{f6}ld");
+    }
+
+    #[test]
+    fn display_width_plain() {
+        assert_eq!(super::display_width("hello"), 5);
+        assert_eq!(super::display_width("h\u{e9}llo"), 5);
+    }
+
+    #[test]
+    fn display_width_styled() {
+        let input = std::format!("{}bold{} text", super::Bold, super::ResetStyle);
+        assert_eq!(super::display_width(&input), 9);
+    }
+
+    #[test]
+    fn display_width_cursor_to() {
+        let input = std::format!("{}", super::CursorTo::AbsoluteXY(3, 4));
+        assert_eq!(super::display_width(&input), 0);
+    }
+
+    assert_escape_output!(cursor_forward_tab_1, super::CursorForwardTab(1), "\x1B[1I");
+    assert_escape_output!(cursor_forward_tab_4, super::CursorForwardTab(4), "\x1B[4I");
+    assert_escape_output!(cursor_backward_tab_1, super::CursorBackwardTab(1), "\x1B[1Z");
+    assert_escape_output!(cursor_backward_tab_4, super::CursorBackwardTab(4), "\x1B[4Z");
+    assert_escape_output!(set_tab_stop, super::SetTabStop, "\x1BH");
+    assert_escape_output!(clear_tab_stop, super::ClearTabStop, "\x1B[0g");
+    assert_escape_output!(clear_all_tab_stops, super::ClearAllTabStops, "\x1B[3g");
+
+    const CURSOR_HIDE: &str = super::CursorHide::SEQUENCE;
+
+    #[test]
+    fn sequence_const() {
+        assert_eq!(CURSOR_HIDE, "\x1B[?25l");
+        assert_eq!(super::CursorHide::SEQUENCE, super::CursorHide.to_string());
+        assert_eq!(super::EraseScreen::SEQUENCE, super::EraseScreen.to_string());
+        assert_eq!(super::Beep::SEQUENCE, super::Beep.to_string());
+    }
+
+    assert_escape_output!(full_reset, super::FullReset, "\x1Bc");
+    assert_escape_output!(soft_reset, super::SoftReset, "\x1B[!p");
+
+    #[test]
+    #[allow(deprecated)]
+    fn clear_screen_deprecated() {
+        assert_eq!(super::ClearScreen.to_string(), super::FullReset.to_string());
+    }
+
+    assert_escape_output!(enable_line_wrap, super::EnableLineWrap, "\x1B[?7h");
+    assert_escape_output!(disable_line_wrap, super::DisableLineWrap, "\x1B[?7l");
+
+    assert_escape_output!(repeat_last_1, super::RepeatLast(1), "\x1B[1b");
+    assert_escape_output!(repeat_last_80, super::RepeatLast(80), "\x1B[80b");
+
+    assert_escape_output!(push_kitty_keyboard_1, super::PushKittyKeyboard(1), "\x1B[>1u");
+    assert_escape_output!(push_kitty_keyboard_15, super::PushKittyKeyboard(15), "\x1B[>15u");
+    assert_escape_output!(pop_kitty_keyboard, super::PopKittyKeyboard, "\x1B[<u");
+    assert_escape_output!(query_kitty_keyboard, super::QueryKittyKeyboard, "\x1B[?u");
+
+    assert_escape_output!(enable_focus_reporting, super::EnableFocusReporting, "\x1B[?1004h");
+    assert_escape_output!(disable_focus_reporting, super::DisableFocusReporting, "\x1B[?1004l");
+
+    #[test]
+    fn parse_focus_event() {
+        assert_eq!(super::parse_focus_event(b"\x1B[I"), Some(true));
+        assert_eq!(super::parse_focus_event(b"\x1B[O"), Some(false));
+        assert_eq!(super::parse_focus_event(b"\x1B[X"), None);
+        assert_eq!(super::parse_focus_event(b"\x1B[I\x1B[O"), None);
+    }
+
+    assert_escape_output!(erase_scrollback, super::EraseScrollback, "\x1B[3J");
+    assert_escape_output!(clear_screen_and_scrollback, super::ClearScreenAndScrollback, "\x1B[2J\x1B[3J");
+
+    assert_escape_output!(
+        set_working_directory,
+        super::SetWorkingDirectory {
+            host: "localhost",
+            path: "/home/user"
+        },
+        "\x1B]7;file://localhost/home/user\x07"
+    );
+    assert_escape_output!(
+        set_working_directory_encoded,
+        super::SetWorkingDirectory {
+            host: "localhost",
+            path: "/home/user/my files"
+        },
+        "\x1B]7;file://localhost/home/user/my%20files\x07"
+    );
+
+    assert_escape_output!(notify, super::Notify("Build complete"), "\x1B]9;Build complete\x07");
+    assert_escape_output!(notify_empty, super::Notify(""), "\x1B]9;\x07");
+    assert_escape_output!(notify_titled, super::NotifyTitled { title: "Build", body: "Complete" }, "\x1B]777;notify;Build;Complete\x07");
+    assert_escape_output!(notify_titled_empty, super::NotifyTitled { title: "", body: "" }, "\x1B]777;notify;;\x07");
+
+    assert_escape_output!(csi, super::Csi::new("38:2:255:0:0", "", b'm').unwrap(), "\x1B[38:2:255:0:0m");
+    assert_escape_output!(csi_intermediate, super::Csi::new("2", " ", b'q').unwrap(), "\x1B[2 q");
+
+    #[test]
+    fn csi_invalid_final_byte() {
+        assert_eq!(super::Csi::new("1", "", b'1'), None);
+        assert_eq!(super::Csi::new("1", "", 0x7F), None);
+    }
+
+    assert_escape_output!(enable_line_drawing, super::EnableLineDrawing, "\x1B(0");
+    assert_escape_output!(disable_line_drawing, super::DisableLineDrawing, "\x1B(B");
+
+    assert_escape_output!(erase_lines_full_1, super::EraseLinesFull(1), "\x1B[2K");
+    assert_escape_output!(erase_lines_full_2, super::EraseLinesFull(2), "\x1B[2K\x1B[1A\x1B[2K");
+
+    #[test]
+    fn erase_lines_full_compared_to_erase_lines() {
+        let full = super::EraseLinesFull(2).to_string();
+        let partial = super::EraseLines(2).to_string();
+
+        assert_eq!(full.replace("\x1B[2K", "\x1B[1000D\x1B[K"), partial);
+    }
+
+    assert_escape_output!(clear_current_line, super::ClearCurrentLine, "\r\x1B[2K");
+
+    assert_escape_output!(window_op_resize, super::WindowOp::Resize { rows: 24, cols: 80 }, "\x1B[8;24;80t");
+    assert_escape_output!(window_op_maximize, super::WindowOp::Maximize, "\x1B[9;1t");
+    assert_escape_output!(window_op_unmaximize, super::WindowOp::Unmaximize, "\x1B[9;0t");
+    assert_escape_output!(window_op_report_size_chars, super::WindowOp::ReportSizeChars, "\x1B[18t");
+    assert_escape_output!(window_op_report_size_pixels, super::WindowOp::ReportSizePixels, "\x1B[14t");
+
+    assert_escape_output!(
+        link_with_id,
+        super::LinkWithId {
+            id: "link1",
+            url: "https://example.com",
+            text: "click me"
+        },
+        "\x1B]8;id=link1;https://example.com\x07click me\x1B]8;;\x07"
+    );
+
+    assert_escape_output!(begin_synchronized_update, super::BeginSynchronizedUpdate, "\x1B[?2026h");
+    assert_escape_output!(end_synchronized_update, super::EndSynchronizedUpdate, "\x1B[?2026l");
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn sync_guard() {
+        let mut buf = Vec::new();
+
+        {
+            let mut guard = super::SyncGuard::new(&mut buf).unwrap();
+            write!(guard, "frame").unwrap();
+        }
+
+        assert_eq!(buf, b"\x1B[?2026hframe\x1B[?2026l");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn render_cursor_up() {
+        assert_eq!(super::render(super::CursorUp(2)), "\x1B[2A");
+    }
+
+    assert_escape_output!(reverse_screen, super::ReverseScreen, "\x1B[?5h");
+    assert_escape_output!(normal_screen, super::NormalScreen, "\x1B[?5l");
+
+    assert_escape_output!(enable_cursor_blink, super::EnableCursorBlink, "\x1B[?12h");
+    assert_escape_output!(disable_cursor_blink, super::DisableCursorBlink, "\x1B[?12l");
+
+    assert_escape_output!(sgr_empty, super::Sgr::new(), "");
+    assert_escape_output!(sgr_bold_red, super::Sgr::new().bold().fg(super::Color::Red), "\x1B[1;31m");
+    assert_escape_output!(sgr_bold_red_on_black, super::Sgr::new().bold().fg(super::Color::Red).bg(super::BgColor::Black), "\x1B[1;31;40m");
+    assert_escape_output!(
+        sgr_all_attributes,
+        super::Sgr::new().bold().dim().italic().underline().blink().inverse().hidden().strikethrough(),
+        "\x1B[1;2;3;4;5;7;8;9m"
+    );
+
+    #[cfg(feature = "serde")]
+    macro_rules! assert_serde_round_trip {
+        ($name:ident, $value:expr, $json:expr) => {
+            #[test]
+            fn $name() {
+                let json = serde_json::to_string(&$value).unwrap();
+                assert_eq!(json, $json);
+                assert_eq!(serde_json::from_str(&json).ok(), Some($value));
+            }
+        };
+    }
+
+    #[cfg(feature = "serde")]
+    assert_serde_round_trip!(serde_cursor_to, super::CursorTo::AbsoluteXY(3, 4), r#"{"AbsoluteXY":[3,4]}"#);
+    #[cfg(feature = "serde")]
+    assert_serde_round_trip!(serde_cursor_move, super::CursorMove::X(-2), r#"{"X":-2}"#);
+    #[cfg(feature = "serde")]
+    assert_serde_round_trip!(serde_color_256, super::Color256(200), "200");
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_sgr() {
+        let sgr = super::Sgr::new().bold().fg(super::Color::Red);
+        assert_eq!(serde_json::to_string(&sgr).unwrap(), "[1,31]");
+        assert_eq!(serde_json::from_str::<super::Sgr>("[1,31]").unwrap(), sgr);
+        assert_eq!(serde_json::from_str::<super::Sgr>("[]").unwrap(), super::Sgr::new());
+
+        let full = "[1,2,3,4,5,7,9,1,2,3,4,5,7,9,1,2]";
+        assert_eq!(serde_json::from_str::<super::Sgr>(full).unwrap().to_string(), "\x1B[1;2;3;4;5;7;9;1;2;3;4;5;7;9;1;2m");
+        assert!(serde_json::from_str::<super::Sgr>("[1,2,3,4,5,7,9,1,2,3,4,5,7,9,1,2,3]").is_err());
+        assert!(serde_json::from_str::<super::Sgr>(r#"{"codes":[1],"len":1}"#).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    assert_serde_round_trip!(serde_color_rgb, super::ColorRgb::new(255, 128, 0), r#"{"r":255,"g":128,"b":0}"#);
+
+    assert_escape_output!(erase_rectangle, super::EraseRectangle { top: 0, left: 0, bottom: 2, right: 4 }, "\x1B[1;1;3;5$z");
+    assert_escape_output!(
+        erase_rectangle_offset,
+        super::EraseRectangle {
+            top: 4,
+            left: 9,
+            bottom: 6,
+            right: 13
+        },
+        "\x1B[5;10;7;14$z"
+    );
+    assert_escape_output!(
+        erase_rectangle_max,
+        super::EraseRectangle {
+            top: u16::MAX,
+            left: u16::MAX,
+            bottom: u16::MAX,
+            right: u16::MAX
+        },
+        "\x1B[65536;65536;65536;65536$z"
+    );
+
+    assert_escape_output!(fill_rectangle, super::FillRectangle::new('*', 0, 0, 2, 4).unwrap(), "\x1B[42;1;1;3;5$x");
+    assert_escape_output!(
+        fill_rectangle_max,
+        super::FillRectangle::new('*', u16::MAX, u16::MAX, u16::MAX, u16::MAX).unwrap(),
+        "\x1B[42;65536;65536;65536;65536$x"
+    );
+
+    #[test]
+    fn fill_rectangle_control_character() {
+        assert_eq!(super::FillRectangle::new('\n', 0, 0, 2, 4), None);
+        assert_eq!(super::FillRectangle::new('\x1B', 0, 0, 2, 4), None);
+        assert_eq!(super::FillRectangle::new('\x7F', 0, 0, 2, 4), None);
+        assert_eq!(super::FillRectangle::new('\u{9B}', 0, 0, 2, 4), None);
+    }
+
+    #[test]
+    fn fill_rectangle_printable_range() {
+        assert!(super::FillRectangle::new(' ', 0, 0, 2, 4).is_some());
+        assert!(super::FillRectangle::new('~', 0, 0, 2, 4).is_some());
+        assert!(super::FillRectangle::new('\u{A0}', 0, 0, 2, 4).is_some());
+        assert!(super::FillRectangle::new('ÿ', 0, 0, 2, 4).is_some());
+        assert_eq!(super::FillRectangle::new('☃', 0, 0, 2, 4), None);
+        assert_eq!(super::FillRectangle::new('Ā', 0, 0, 2, 4), None);
+    }
+
+    assert_escape_output!(set_palette_color, super::SetPaletteColor { index: 1, r: 255, g: 0, b: 0 }, "\x1B]4;1;rgb:ff/00/00\x07");
+    assert_escape_output!(reset_palette_color, super::ResetPaletteColor(1), "\x1B]104;1\x07");
+    assert_escape_output!(reset_all_palette_colors, super::ResetAllPaletteColors, "\x1B]104\x07");
+
+    assert_escape_output!(set_default_foreground, super::SetDefaultForeground { r: 255, g: 255, b: 255 }, "\x1B]10;rgb:ff/ff/ff\x07");
+    assert_escape_output!(set_default_background, super::SetDefaultBackground { r: 0x1e, g: 0x1e, b: 0x2e }, "\x1B]11;rgb:1e/1e/2e\x07");
+    assert_escape_output!(reset_default_foreground, super::ResetDefaultForeground, "\x1B]110\x07");
+    assert_escape_output!(reset_default_background, super::ResetDefaultBackground, "\x1B]111\x07");
+
+    assert_escape_output!(dec_private_mode_set, super::DecPrivateMode::BracketedPaste.set(), "\x1B[?2004h");
+    assert_escape_output!(dec_private_mode_reset, super::DecPrivateMode::BracketedPaste.reset(), "\x1B[?2004l");
+    assert_escape_output!(dec_private_mode_cursor_visible_set, super::DecPrivateMode::CursorVisible.set(), "\x1B[?25h");
+    assert_escape_output!(dec_private_mode_other_set, super::DecPrivateMode::Other(2027).set(), "\x1B[?2027h");
+    assert_escape_output!(dec_private_mode_other_reset, super::DecPrivateMode::Other(2027).reset(), "\x1B[?2027l");
+
+    #[test]
+    fn dec_private_mode_sequence() {
+        use super::DecPrivateMode;
+
+        let toggles = [
+            (super::CursorHide::SEQUENCE, super::CursorHide.to_string(), DecPrivateMode::CursorVisible.reset().to_string()),
+            (super::CursorShow::SEQUENCE, super::CursorShow.to_string(), DecPrivateMode::CursorVisible.set().to_string()),
+            (super::EnableCursorBlink::SEQUENCE, super::EnableCursorBlink.to_string(), DecPrivateMode::CursorBlink.set().to_string()),
+            (
+                super::DisableCursorBlink::SEQUENCE,
+                super::DisableCursorBlink.to_string(),
+                DecPrivateMode::CursorBlink.reset().to_string(),
+            ),
+            (
+                super::EnterAlternativeScreen::SEQUENCE,
+                super::EnterAlternativeScreen.to_string(),
+                DecPrivateMode::AlternativeScreen.set().to_string(),
+            ),
+            (
+                super::ExitAlternativeScreen::SEQUENCE,
+                super::ExitAlternativeScreen.to_string(),
+                DecPrivateMode::AlternativeScreen.reset().to_string(),
+            ),
+            (
+                super::EnableBracketedPaste::SEQUENCE,
+                super::EnableBracketedPaste.to_string(),
+                DecPrivateMode::BracketedPaste.set().to_string(),
+            ),
+            (
+                super::DisableBracketedPaste::SEQUENCE,
+                super::DisableBracketedPaste.to_string(),
+                DecPrivateMode::BracketedPaste.reset().to_string(),
+            ),
+            (
+                super::EnableMouseTracking::SEQUENCE,
+                super::EnableMouseTracking.to_string(),
+                DecPrivateMode::MouseTracking.set().to_string(),
+            ),
+            (
+                super::DisableMouseTracking::SEQUENCE,
+                super::DisableMouseTracking.to_string(),
+                DecPrivateMode::MouseTracking.reset().to_string(),
+            ),
+            (
+                super::EnableMouseButtonEvent::SEQUENCE,
+                super::EnableMouseButtonEvent.to_string(),
+                DecPrivateMode::MouseButtonEvent.set().to_string(),
+            ),
+            (
+                super::DisableMouseButtonEvent::SEQUENCE,
+                super::DisableMouseButtonEvent.to_string(),
+                DecPrivateMode::MouseButtonEvent.reset().to_string(),
+            ),
+            (
+                super::EnableMouseAnyEvent::SEQUENCE,
+                super::EnableMouseAnyEvent.to_string(),
+                DecPrivateMode::MouseAnyEvent.set().to_string(),
+            ),
+            (
+                super::DisableMouseAnyEvent::SEQUENCE,
+                super::DisableMouseAnyEvent.to_string(),
+                DecPrivateMode::MouseAnyEvent.reset().to_string(),
+            ),
+            (super::EnableSgrMouse::SEQUENCE, super::EnableSgrMouse.to_string(), DecPrivateMode::SgrMouse.set().to_string()),
+            (super::DisableSgrMouse::SEQUENCE, super::DisableSgrMouse.to_string(), DecPrivateMode::SgrMouse.reset().to_string()),
+            (super::EnableLineWrap::SEQUENCE, super::EnableLineWrap.to_string(), DecPrivateMode::LineWrap.set().to_string()),
+            (super::DisableLineWrap::SEQUENCE, super::DisableLineWrap.to_string(), DecPrivateMode::LineWrap.reset().to_string()),
+            (
+                super::EnableFocusReporting::SEQUENCE,
+                super::EnableFocusReporting.to_string(),
+                DecPrivateMode::FocusReporting.set().to_string(),
+            ),
+            (
+                super::DisableFocusReporting::SEQUENCE,
+                super::DisableFocusReporting.to_string(),
+                DecPrivateMode::FocusReporting.reset().to_string(),
+            ),
+            (
+                super::BeginSynchronizedUpdate::SEQUENCE,
+                super::BeginSynchronizedUpdate.to_string(),
+                DecPrivateMode::SynchronizedUpdate.set().to_string(),
+            ),
+            (
+                super::EndSynchronizedUpdate::SEQUENCE,
+                super::EndSynchronizedUpdate.to_string(),
+                DecPrivateMode::SynchronizedUpdate.reset().to_string(),
+            ),
+            (super::ReverseScreen::SEQUENCE, super::ReverseScreen.to_string(), DecPrivateMode::ReverseScreen.set().to_string()),
+            (super::NormalScreen::SEQUENCE, super::NormalScreen.to_string(), DecPrivateMode::ReverseScreen.reset().to_string()),
+        ];
+
+        for (sequence, display, mode) in toggles.iter() {
+            assert_eq!(sequence, display);
+            assert_eq!(sequence, mode);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    static ENV_LOCK: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
+
+    /// Sets environment variables for the duration of a test, and restores the previous values when dropped. Tests holding a guard are serialized, since the environment is shared by all threads.
+    #[cfg(feature = "std")]
+    struct EnvGuard {
+        saved: Vec<(&'static str, Option<std::ffi::OsString>)>,
+    }
+
+    #[cfg(feature = "std")]
+    impl EnvGuard {
+        fn new(vars: &[(&'static str, Option<&str>)]) -> EnvGuard {
+            use core::sync::atomic::Ordering;
+
+            while ENV_LOCK.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+                std::thread::yield_now();
+            }
+
+            let saved = vars.iter().map(|&(name, _)| (name, std::env::var_os(name))).collect();
+
+            for &(name, value) in vars {
+                match value {
+                    Some(value) => std::env::set_var(name, value),
+                    None => std::env::remove_var(name),
+                }
+            }
+
+            EnvGuard { saved }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl Drop for EnvGuard {
+        fn drop(&mut self) {
+            for (name, value) in &self.saved {
+                match value {
+                    Some(value) => std::env::set_var(name, value),
+                    None => std::env::remove_var(name),
+                }
+            }
+
+            ENV_LOCK.store(false, core::sync::atomic::Ordering::Release);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    struct ChunkedReader<'a>(&'a [&'a [u8]]);
+
+    #[cfg(feature = "std")]
+    impl<'a> std::io::Read for ChunkedReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.split_first() {
+                Some((chunk, rest)) => {
+                    buf[..chunk.len()].copy_from_slice(chunk);
+                    self.0 = rest;
+                    Ok(chunk.len())
+                }
+                None => Ok(0),
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn query_cursor_position() {
+        let mut out = Vec::new();
+        let mut inp = ChunkedReader(&[b"\x1B[5;10R"]);
+
+        assert_eq!(super::query_cursor_position(&mut out, &mut inp).unwrap(), (5, 10));
+        assert_eq!(out, b"\x1B[6n");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn query_cursor_position_split_and_intermixed() {
+        let mut out = Vec::new();
+        let mut inp = ChunkedReader(&[b"ab\x1B[A\x1B[5", b";1", b"0R"]);
+
+        assert_eq!(super::query_cursor_position(&mut out, &mut inp).unwrap(), (5, 10));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn query_cursor_position_eof() {
+        let mut out = Vec::new();
+        let mut inp = ChunkedReader(&[b"\x1B[5;1"]);
+
+        let err = super::query_cursor_position(&mut out, &mut inp).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    assert_escape_output!(overline, super::Overline, "\x1B[53m");
+    assert_escape_output!(overline_off, super::OverlineOff, "\x1B[55m");
+
+    assert_escape_output!(underline_style_none, super::UnderlineStyle::None, "\x1B[4:0m");
+    assert_escape_output!(underline_style_single, super::UnderlineStyle::Single, "\x1B[4:1m");
+    assert_escape_output!(underline_style_double, super::UnderlineStyle::Double, "\x1B[4:2m");
+    assert_escape_output!(underline_style_curly, super::UnderlineStyle::Curly, "\x1B[4:3m");
+    assert_escape_output!(underline_style_dotted, super::UnderlineStyle::Dotted, "\x1B[4:4m");
+    assert_escape_output!(underline_style_dashed, super::UnderlineStyle::Dashed, "\x1B[4:5m");
+
+    assert_escape_output!(underline_color_256, super::UnderlineColor256(196), "\x1B[58;5;196m");
+    assert_escape_output!(underline_color_rgb, super::UnderlineColorRgb { r: 255, g: 0, b: 0 }, "\x1B[58;2;255;0;0m");
+    assert_escape_output!(underline_color_reset, super::UnderlineColorReset, "\x1B[59m");
+
+    #[test]
+    fn ansi_macro() {
+        assert_eq!(crate::ansi!(super::Bold, super::Color::Green).to_string(), std::format!("{}{}", super::Bold, super::Color::Green));
+        assert_eq!(
+            crate::ansi!(super::CursorHide, super::Color::Red, "text", super::ResetStyle,).to_string(),
+            "\x1B[?25l\x1B[31mtext\x1B[0m"
+        );
+        assert_eq!(crate::ansi!().to_string(), "");
+    }
+
+    assert_escape_output!(cursor_to_row_col_1_1, super::CursorTo::RowCol(1, 1), "\x1B[1;1H");
+    assert_escape_output!(cursor_to_row_col_5_10, super::CursorTo::RowCol(5, 10), "\x1B[5;10H");
+
+    #[test]
+    fn cursor_to_row_col_top_left() {
+        assert_eq!(super::CursorTo::RowCol(1, 1).to_string(), super::CursorTo::TopLeft.to_string());
+        assert_eq!(super::CursorTo::RowCol(5, 10).to_string(), super::CursorTo::AbsoluteXY(9, 4).to_string());
+    }
+
+    assert_escape_output!(rapid_blink, super::RapidBlink, "\x1B[6m");
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn alternative_screen_guard() {
+        let mut buf = Vec::new();
+
+        {
+            let mut guard = super::AlternativeScreenGuard::new(&mut buf, true).unwrap();
+            write!(guard, "ui").unwrap();
+        }
+
+        assert_eq!(buf, b"\x1B[?1049h\x1B[?25lui\x1B[?1049l\x1B[?25h");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn alternative_screen_guard_visible_cursor() {
+        let mut buf = Vec::new();
+        drop(super::AlternativeScreenGuard::new(&mut buf, false).unwrap());
+
+        assert_eq!(buf, b"\x1B[?1049h\x1B[?1049l");
+    }
+
+    assert_escape_output!(erase_display_below, super::EraseDisplay::Below, "\x1B[0J");
+    assert_escape_output!(erase_display_above, super::EraseDisplay::Above, "\x1B[1J");
+    assert_escape_output!(erase_display_all, super::EraseDisplay::All, "\x1B[2J");
+    assert_escape_output!(erase_display_scrollback, super::EraseDisplay::Scrollback, "\x1B[3J");
+
+    #[test]
+    fn erase_display_from() {
+        assert_eq!(super::EraseDisplay::from(super::EraseDown), super::EraseDisplay::Below);
+        assert_eq!(super::EraseDisplay::from(super::EraseUp), super::EraseDisplay::Above);
+        assert_eq!(super::EraseDisplay::from(super::EraseScreen), super::EraseDisplay::All);
+        assert_eq!(super::EraseDisplay::from(super::EraseScrollback), super::EraseDisplay::Scrollback);
+    }
+
+    assert_escape_output!(erase_in_line_to_end, super::EraseInLine::ToEnd, "\x1B[0K");
+    assert_escape_output!(erase_in_line_to_start, super::EraseInLine::ToStart, "\x1B[1K");
+    assert_escape_output!(erase_in_line_all, super::EraseInLine::All, "\x1B[2K");
+
+    #[test]
+    fn erase_in_line_from() {
+        assert_eq!(super::EraseInLine::from(super::EraseEndLine), super::EraseInLine::ToEnd);
+        assert_eq!(super::EraseInLine::from(super::EraseStartLine), super::EraseInLine::ToStart);
+        assert_eq!(super::EraseInLine::from(super::EraseLine), super::EraseInLine::All);
+    }
+
+    const CMDS: [super::CursorUp; 2] = [super::CursorUp::new(1), super::CursorUp::new(5)];
+    const HIDE: super::CursorHide = super::CursorHide;
+
+    #[test]
+    fn const_new() {
+        assert_eq!(CMDS, [super::CursorUp(1), super::CursorUp(5)]);
+        assert_eq!(HIDE.to_string(), "\x1B[?25l");
+        assert_eq!(super::ScrollDownBy::new(3), super::ScrollDownBy(3));
+    }
+
+    #[test]
+    fn color_rgb_from() {
+        let expected = super::ColorRgb::new(255, 128, 0).to_string();
+
+        assert_eq!(super::ColorRgb::from((255, 128, 0)).to_string(), expected);
+        assert_eq!(super::ColorRgb::from([255, 128, 0]).to_string(), expected);
+
+        let color: super::ColorRgb = (255, 128, 0).into();
+        assert_eq!(color.to_string(), expected);
+    }
+
+    #[test]
+    fn xterm256_to_rgb() {
+        assert_eq!(super::xterm256_to_rgb(1), (205, 0, 0));
+        assert_eq!(super::xterm256_to_rgb(16), (0, 0, 0));
+        assert_eq!(super::xterm256_to_rgb(110), (135, 175, 215));
+        assert_eq!(super::xterm256_to_rgb(196), (255, 0, 0));
+        assert_eq!(super::xterm256_to_rgb(231), (255, 255, 255));
+        assert_eq!(super::xterm256_to_rgb(232), (8, 8, 8));
+        assert_eq!(super::xterm256_to_rgb(244), (128, 128, 128));
+        assert_eq!(super::xterm256_to_rgb(255), (238, 238, 238));
+    }
+
+    #[test]
+    fn rgb_to_xterm256() {
+        assert_eq!(super::rgb_to_xterm256(255, 0, 0), 196);
+        assert_eq!(super::rgb_to_xterm256(255, 255, 255), 231);
+        assert_eq!(super::rgb_to_xterm256(0, 0, 0), 16);
+        assert_eq!(super::rgb_to_xterm256(128, 128, 128), 244);
+        assert_eq!(super::rgb_to_xterm256(135, 175, 215), 110);
+        assert_eq!(super::rgb_to_xterm256(130, 170, 220), 110);
+    }
+
+    #[test]
+    fn rgb_to_xterm256_round_trip() {
+        for index in 16..=255 {
+            let (r, g, b) = super::xterm256_to_rgb(index);
+            assert_eq!(super::xterm256_to_rgb(super::rgb_to_xterm256(r, g, b)), (r, g, b));
+        }
+    }
+
+    assert_escape_output!(downgrade_fg_true_color, super::downgrade_fg((255, 128, 0), super::ColorLevel::TrueColor), "\x1B[38;2;255;128;0m");
+    assert_escape_output!(downgrade_fg_ansi_256, super::downgrade_fg((255, 0, 0), super::ColorLevel::Ansi256), "\x1B[38;5;196m");
+    assert_escape_output!(downgrade_fg_ansi_16_red, super::downgrade_fg((250, 10, 10), super::ColorLevel::Ansi16), "\x1B[91m");
+    assert_escape_output!(downgrade_fg_ansi_16_dark_red, super::downgrade_fg((180, 20, 20), super::ColorLevel::Ansi16), "\x1B[31m");
+    assert_escape_output!(downgrade_fg_ansi_16_black, super::downgrade_fg((10, 10, 10), super::ColorLevel::Ansi16), "\x1B[30m");
+
+    assert_escape_output!(sixel, super::Sixel("q#0;2;0;0;0#0~~@@vv@@~~$-"), "\x1BPq#0;2;0;0;0#0~~@@vv@@~~$-\x1B\\");
+    assert_escape_output!(sixel_empty, super::Sixel(""), "\x1BP\x1B\\");
+
+    assert_escape_output!(
+        kitty_image_single_chunk,
+        super::KittyImage {
+            data: b"\xFF\x00\x00",
+            format: super::KittyFormat::Rgb,
+            width: 1,
+            height: 1,
+        },
+        "\x1B_Ga=T,f=24,s=1,v=1,m=0;/wAA\x1B\\"
+    );
+
+    #[test]
+    fn kitty_image_two_chunks() {
+        let data = [0u8; 3076];
+        let image = super::KittyImage {
+            data: &data,
+            format: super::KittyFormat::Rgba,
+            width: 769,
+            height: 1,
+        };
+
+        let mut expected = String::from("\x1B_Ga=T,f=32,s=769,v=1,m=1;");
+        expected.push_str(&"A".repeat(4096));
+        expected.push_str("\x1B\\\x1B_Gm=0;AAAAAA==\x1B\\");
+
+        assert_eq!(image.to_string(), expected);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn color_level_from() {
+        use super::ColorLevel;
+
+        assert_eq!(super::color_level_from(Some("truecolor"), Some("xterm-256color")), ColorLevel::TrueColor);
+        assert_eq!(super::color_level_from(Some("24bit"), None), ColorLevel::TrueColor);
+        assert_eq!(super::color_level_from(None, Some("xterm-256color")), ColorLevel::Ansi256);
+        assert_eq!(super::color_level_from(Some("yes"), Some("screen-256color")), ColorLevel::Ansi256);
+        assert_eq!(super::color_level_from(None, Some("xterm")), ColorLevel::Ansi16);
+        assert_eq!(super::color_level_from(None, None), ColorLevel::Ansi16);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn detect_color_level() {
+        use super::ColorLevel;
+
+        let _env = EnvGuard::new(&[("COLORTERM", Some("truecolor")), ("TERM", Some("xterm-256color"))]);
+        assert_eq!(super::detect_color_level(), ColorLevel::TrueColor);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn reset_terminal() {
+        let mut buf = Vec::new();
+
+        super::reset_terminal(&mut buf).unwrap();
+
+        assert_eq!(buf, b"\x1B[?25h\x1B[?1049l\x1B[0m\x1B[?1000l\x1B[?1002l\x1B[?1003l\x1B[?1006l\x1B[?2004l\x1B[?1004l");
+    }
+
+    assert_escape_output!(cursor_move_x_min, super::CursorMove::X(i16::MIN), "\x1B[32768D");
+    assert_escape_output!(cursor_move_y_min, super::CursorMove::Y(i16::MIN), "\x1B[32768A");
+
+    #[test]
+    fn counting_sink() {
+        use core::fmt::Write;
+
+        let mut sink = super::CountingSink::default();
+
+        write!(&mut sink, "{}", super::CursorUp(10)).unwrap();
+        assert_eq!(sink.bytes, super::CursorUp(10).to_string().len());
+
+        write!(&mut sink, "{}", super::EraseLines(5)).unwrap();
+        assert_eq!(sink.bytes, super::CursorUp(10).to_string().len() + super::EraseLines(5).to_string().len());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hyperlink_support_from() {
+        assert!(super::hyperlink_support_from(Some("xterm-256color"), Some("iTerm.app"), None, false));
+        assert!(super::hyperlink_support_from(Some("xterm-kitty"), None, None, false));
+        assert!(super::hyperlink_support_from(Some("xterm-256color"), None, Some("6003"), false));
+        assert!(super::hyperlink_support_from(Some("xterm-256color"), None, None, true));
+        assert!(!super::hyperlink_support_from(Some("xterm-256color"), None, Some("4205"), false));
+        assert!(!super::hyperlink_support_from(Some("xterm"), None, None, false));
+        assert!(!super::hyperlink_support_from(Some("dumb"), None, Some("6003"), false));
+        assert!(!super::hyperlink_support_from(None, None, None, false));
+        assert!(super::hyperlink_support_from(None, None, None, true));
+        assert!(super::hyperlink_support_from(None, Some("iTerm.app"), None, false));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn link_or_plain() {
+        {
+            let _env = EnvGuard::new(&[("TERM", None), ("TERM_PROGRAM", None), ("VTE_VERSION", None), ("WT_SESSION", Some("1"))]);
+            assert_eq!(
+                super::link_or_plain("https://example.com", "example").to_string(),
+                "\x1B]8;;https://example.com\x1B\\example\x1B]8;;\x1B\\"
+            );
+        }
+
+        {
+            let _env = EnvGuard::new(&[("TERM", Some("dumb")), ("TERM_PROGRAM", None), ("VTE_VERSION", None), ("WT_SESSION", None)]);
+            assert_eq!(super::link_or_plain("https://example.com", "example").to_string(), "example");
+        }
+    }
+
+    assert_escape_output!(erase_lines_safe_0, super::EraseLinesSafe(0), "");
+    assert_escape_output!(erase_lines_safe_1, super::EraseLinesSafe(1), "\x1B7\x1B[1000D\x1B[K\x1B8");
+    assert_escape_output!(erase_lines_safe_2, super::EraseLinesSafe(2), "\x1B7\x1B[1000D\x1B[K\x1B[1A\x1B[1000D\x1B[K\x1B8");
+
+    #[test]
+    fn erase_lines_safe_compared_to_erase_lines() {
+        for count in 1..5 {
+            let expected = std::format!("\x1B7{}\x1B8", super::EraseLines(count));
+            assert_eq!(super::EraseLinesSafe(count).to_string(), expected);
+        }
+    }
+
+    assert_escape_output!(reference_to_unit_code, &super::CursorHide, "\x1B[?25l");
+    assert_escape_output!(reference_to_count_code, &super::CursorUp(3), "\x1B[3A");
+    assert_escape_output!(sequence_of_references, super::Sequence::new().then(&super::Bold).then(&super::CursorUp(2)), "\x1B[1m\x1B[2A");
+
+    #[test]
+    fn trait_objects() {
+        let codes: [&dyn core::fmt::Display; 3] = [&super::CursorHide, &super::Color::Red, &super::EraseLines(1)];
+        let output: String = codes.iter().map(|code| code.to_string()).collect();
+        assert_eq!(output, "\x1B[?25l\x1B[31m\x1B[1000D\x1B[K");
+
+        let sequence = super::Sequence::new().then(codes[0]).then(codes[1]);
+        assert_eq!(sequence.to_string(), "\x1B[?25l\x1B[31m");
+
+        let mut buf = [0u8; 16];
+        let len = super::EscapeBytes::write_bytes(codes[0], &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"\x1B[?25l");
+    }
+
+    #[test]
+    fn palette() {
+        let mut colors = [(0, 0, 0); 16];
+        colors[0] = (0x1d, 0x1f, 0x21);
+        colors[15] = (0xff, 0xff, 0xff);
+
+        let output = super::Palette { colors }.to_string();
+
+        assert!(output.starts_with("\x1B]4;0;rgb:1d/1f/21\x07\x1B]4;1;rgb:00/00/00\x07"));
+        assert!(output.ends_with("\x1B]4;14;rgb:00/00/00\x07\x1B]4;15;rgb:ff/ff/ff\x07"));
+        assert_eq!(output.matches("\x1B]4;").count(), 16);
+    }
+
+    assert_escape_output!(progress_redraw, super::ProgressRedraw { text: "50%" }, "\r\x1B[2K50%");
+
+    assert_escape_output!(cursor_move_xy_order, super::CursorMove::XY(2, 3), "\x1B[2C\x1B[3B");
+    assert_escape_output!(cursor_move_y_then_x_order, super::CursorMove::YThenX(2, 3), "\x1B[3B\x1B[2C");
+    assert_escape_output!(cursor_move_y_then_x_negative, super::CursorMove::YThenX(-2, -3), "\x1B[3A\x1B[2D");
+
+    #[test]
+    fn try_from_str() {
+        use super::{CursorBackwardTab, CursorColumn, CursorDown, CursorRow, CursorUp, ParseError};
+        use core::convert::TryFrom;
+
+        assert_eq!(CursorUp::try_from("\x1B[5A"), Ok(CursorUp(5)));
+        assert_eq!(CursorUp::try_from("\x1B[A"), Ok(CursorUp(1)));
+        assert_eq!(CursorUp::try_from("\x1B[5B"), Err(ParseError));
+        assert_eq!(CursorUp::try_from("\x1B[5"), Err(ParseError));
+        assert_eq!(CursorUp::try_from("\x1B[x5A"), Err(ParseError));
+        assert_eq!(CursorUp::try_from("\x1B[99999A"), Err(ParseError));
+        assert_eq!(CursorUp::try_from("[5A"), Err(ParseError));
+        assert_eq!(CursorDown::try_from("\x1B[5B"), Ok(CursorDown(5)));
+        assert_eq!(CursorColumn::try_from("\x1B[12G"), Ok(CursorColumn(12)));
+        assert_eq!(CursorRow::try_from("\x1B[3d"), Ok(CursorRow(3)));
+        assert_eq!(CursorBackwardTab::try_from("\x1B[2Z"), Ok(CursorBackwardTab(2)));
+    }
+
+    #[test]
+    fn try_from_str_round_trip() {
+        use super::CursorForward;
+        use core::convert::TryFrom;
+
+        let code = CursorForward(42);
+        assert_eq!(CursorForward::try_from(code.to_string().as_str()), Ok(code));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn diff_render() {
+        assert_eq!(super::diff_render(&["abc", "def"], &["abc", "def"]), "");
+        assert_eq!(super::diff_render(&["abc", "progress: 10%", "xyz"], &["abc", "progress: 20%", "xyz"]), "\x1B[2;11H2");
+        assert_eq!(super::diff_render(&["hello", "world"], &["hello", "wor"]), "\x1B[2;4H\x1B[K");
+        assert_eq!(super::diff_render(&["hello", "world"], &["hello", "worldwide"]), "\x1B[2;6Hwide");
+        assert_eq!(super::diff_render(&["hello", "world"], &["hello", "there"]), "\x1B[2;1Hthere");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn diff_render_long_line() {
+        let prefix = "a".repeat(65535);
+        assert_eq!(super::diff_render(&[&std::format!("{}b", prefix)], &[&std::format!("{}c", prefix)]), "\x1B[1;65536Hc");
+
+        let prefix = "a".repeat(70000);
+        assert_eq!(super::diff_render(&[&std::format!("{}b", prefix), "x"], &[&std::format!("{}c", prefix), "y"]), "\x1B[2;1Hy");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn diff_render_cleared_line() {
+        assert_eq!(super::diff_render(&["one", "two", "three"], &["one", "", "three"]), "\x1B[2;1H\x1B[K");
+        assert_eq!(super::diff_render(&["one", "two"], &["one"]), "\x1B[2;1H\x1B[K");
+        assert_eq!(super::diff_render(&["one"], &["one", "two"]), "\x1B[2;1Htwo");
+    }
+
+    #[test]
+    fn count_code_default() {
+        assert_eq!(super::CursorUp::default(), super::CursorUp(1));
+        assert_eq!(super::CursorColumn::default(), super::CursorColumn(1));
+        assert_eq!(super::ScrollDownBy::default(), super::ScrollDownBy(1));
+        assert_eq!(super::EraseLines::default(), super::EraseLines(0));
+        assert_eq!(super::EraseLinesSafe::default(), super::EraseLinesSafe(0));
+    }
+
+    #[test]
+    fn sequence_buf() {
+        use core::fmt::Write;
+
+        let mut buf = super::SequenceBuf::<16>::new();
+        assert!(buf.is_empty());
+
+        write!(buf, "{}{}", super::CursorHide, super::Color::Red).unwrap();
+        assert_eq!(buf.as_bytes(), b"\x1B[?25l\x1B[31m");
+        assert_eq!(buf.len(), 11);
+        assert_eq!(buf.to_string(), "\x1B[?25l\x1B[31m");
+
+        buf.clear();
+        assert_eq!(buf.as_str(), "");
+    }
+
+    #[test]
+    fn sequence_buf_overflow() {
+        use core::fmt::Write;
+
+        let mut buf = super::SequenceBuf::<8>::new();
+
+        write!(buf, "{}", super::CursorHide).unwrap();
+        assert!(write!(buf, "{}", super::Color::Red).is_err());
+        assert_eq!(buf.as_bytes(), b"\x1B[?25l");
+
+        write!(buf, "ab").unwrap();
+        assert_eq!(buf.len(), 8);
+        assert!(write!(buf, "c").is_err());
+        assert_eq!(buf.as_str(), "\x1B[?25lab");
+    }
+
+    #[test]
+    fn sequence_buf_eq() {
+        use core::fmt::Write;
+
+        let mut buf = super::SequenceBuf::<8>::new();
+        write!(buf, "{}", super::CursorHide).unwrap();
+        assert_ne!(buf, super::SequenceBuf::new());
+
+        buf.clear();
+        assert_eq!(buf, super::SequenceBuf::new());
+
+        let mut buf = super::SequenceBuf::<4>::new();
+        assert!(write!(buf, "{}", super::CursorHide).is_err());
+        assert_eq!(buf, super::SequenceBuf::new());
+
+        assert_eq!(std::format!("{:?}", buf), r#"SequenceBuf("")"#);
+    }
+
+    assert_escape_output!(conceal, super::Conceal, "\x1B[8m");
+    assert_escape_output!(reveal, super::Reveal, "\x1B[28m");
+    assert_escape_output!(conceal_off, super::ConcealOff, "\x1B[28m");
+
+    #[test]
+    fn conceal_same_as_hidden() {
+        assert_eq!(super::Conceal.to_string(), super::Hidden.to_string());
+        assert_eq!(super::Reveal.to_string(), super::HiddenOff.to_string());
+        assert_eq!(super::ConcealOff.to_string(), super::HiddenOff.to_string());
+    }
+
+    assert_escape_output!(select_font_primary, super::SelectFont(0), "\x1B[10m");
+    assert_escape_output!(select_font_3, super::SelectFont(3), "\x1B[13m");
+    assert_escape_output!(select_font_9, super::SelectFont(9), "\x1B[19m");
+
+    #[test]
+    fn select_font_new() {
+        assert_eq!(super::SelectFont::new(3), Some(super::SelectFont(3)));
+        assert_eq!(super::SelectFont::new(9), Some(super::SelectFont(9)));
+        assert_eq!(super::SelectFont::new(10), None);
+    }
+
+    assert_escape_output!(cursor_to_absolute_y_0, super::CursorTo::AbsoluteY(0), "\x1B[1d");
+    assert_escape_output!(cursor_to_absolute_y_9, super::CursorTo::AbsoluteY(9), "\x1B[10d");
+    assert_escape_output!(cursor_to_absolute_y_max, super::CursorTo::AbsoluteY(u16::MAX), "\x1B[65536d");
+    assert_escape_output!(cursor_to_absolute_x_max, super::CursorTo::AbsoluteX(u16::MAX), "\x1B[65536G");
+    assert_escape_output!(cursor_to_absolute_xy_max, super::CursorTo::AbsoluteXY(u16::MAX, u16::MAX), "\x1B[65536;65536H");
+    assert_escape_output!(cursor_to_fn_max, super::cursor_to(u16::MAX, 0), "\x1B[1;65536H");
+
+    assert_escape_output!(clear_and_home, super::ClearAndHome, "\x1B[2J\x1B[H");
+
+    #[test]
+    fn count_code_saturating() {
+        assert_eq!(super::CursorUp::saturating(100_000), super::CursorUp(65535));
+        assert_eq!(super::CursorForward::saturating(42), super::CursorForward(42));
+        assert_eq!(super::EraseLines::saturating(65535), super::EraseLines(65535));
+        assert_eq!(super::ScrollUpBy::saturating(usize::MAX), super::ScrollUpBy(u16::MAX));
+    }
+
+    assert_escape_output!(enter_tui_mode, super::EnterTuiMode, "\x1B[?1049h\x1B[?25l\x1B[2J");
+    assert_escape_output!(exit_tui_mode, super::ExitTuiMode, "\x1B[?25h\x1B[?1049l");
+
+    #[test]
+    fn tui_mode_components() {
+        assert_eq!(
+            super::EnterTuiMode.to_string(),
+            std::format!("{}{}{}", super::EnterAlternativeScreen, super::CursorHide, super::EraseScreen)
+        );
+        assert_eq!(super::ExitTuiMode.to_string(), std::format!("{}{}", super::CursorShow, super::ExitAlternativeScreen));
+    }
+
+    assert_escape_output!(prompt_start, super::PromptStart, "\x1B]133;A\x07");
+    assert_escape_output!(command_start, super::CommandStart, "\x1B]133;B\x07");
+    assert_escape_output!(command_executed, super::CommandExecuted, "\x1B]133;C\x07");
+    assert_escape_output!(command_finished_success, super::CommandFinished(0), "\x1B]133;D;0\x07");
+    assert_escape_output!(command_finished_failure, super::CommandFinished(127), "\x1B]133;D;127\x07");
+    assert_escape_output!(command_finished_negative, super::CommandFinished(-1), "\x1B]133;D;-1\x07");
+
+    assert_escape_output!(cursor_shape_default, super::CursorShapeDefault, "\x1B[0 q");
+
+    assert_escape_output!(move_to_line_start_0, super::MoveToLineStart(0), "\r");
+    assert_escape_output!(move_to_line_start_1, super::MoveToLineStart(1), "\x1B[1F");
+    assert_escape_output!(move_to_line_start_3, super::MoveToLineStart(3), "\x1B[3F");
+
+    assert_escape_output!(query_dec_mode, super::QueryDecMode(2026), "\x1B[?2026$p");
+    assert_escape_output!(query_dec_mode_cursor_visible, super::QueryDecMode(super::DecPrivateMode::CursorVisible.code()), "\x1B[?25$p");
+
+    #[test]
+    fn parse_dec_mode_reply() {
+        assert_eq!(super::parse_dec_mode_reply(b"\x1B[?2026;1$y"), Some((2026, 1)));
+        assert_eq!(super::parse_dec_mode_reply(b"\x1B[?2026;0$y"), Some((2026, 0)));
+        assert_eq!(super::parse_dec_mode_reply(b"\x1B[?25;2$y"), Some((25, 2)));
+        assert_eq!(super::parse_dec_mode_reply(b"\x1B[?2026$y"), None);
+        assert_eq!(super::parse_dec_mode_reply(b"\x1B[?;1$y"), None);
+        assert_eq!(super::parse_dec_mode_reply(b"\x1B[?2026;1;2$y"), None);
+        assert_eq!(super::parse_dec_mode_reply(b"\x1B[?2026;300$y"), None);
+        assert_eq!(super::parse_dec_mode_reply(b"\x1B[2026;1$y"), None);
+        assert_eq!(super::parse_dec_mode_reply(b"\x1B[?2026;1y"), None);
+    }
 }